@@ -1,8 +1,5 @@
-use compiler::{
-    cli_launcher::CliLauncher,
-    errors::{diagnostic::DiagnosticCtxt, emitter::StdoutEmitter},
-};
+use compiler::cli_launcher::CliLauncher;
 
 fn main() {
-    CliLauncher::launch_with_exit(DiagnosticCtxt::new(Box::new(StdoutEmitter::new())));
+    CliLauncher::launch_with_exit();
 }
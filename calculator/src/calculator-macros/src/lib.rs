@@ -5,7 +5,7 @@ use syn::{
     parse_macro_input, Attribute, Data, DataStruct, DeriveInput, Error, Fields, FieldsNamed, LitStr,
 };
 
-#[proc_macro_derive(IntoDiagnostic, attributes(diagnostic))]
+#[proc_macro_derive(IntoDiagnostic, attributes(diagnostic, primary_span))]
 pub fn into_diag_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = &input.ident;
@@ -14,7 +14,12 @@ pub fn into_diag_derive(input: TokenStream) -> TokenStream {
         Ok(fields) => fields.named,
         Err(err) => return err.to_compile_error().into(),
     };
-    let field_names: Vec<_> = fields.iter().flat_map(|f| f.ident.as_ref()).collect();
+    // Field with `#[primary_span]` is not a part of the message, its the location of the error
+    let (span_fields, msg_fields): (Vec<_>, Vec<_>) = fields
+        .iter()
+        .partition(|f| f.attrs.iter().any(|a| a.path().is_ident("primary_span")));
+    let field_names: Vec<_> = msg_fields.iter().flat_map(|f| f.ident.as_ref()).collect();
+    let span_names: Vec<_> = span_fields.iter().flat_map(|f| f.ident.as_ref()).collect();
 
     let diagnostic_attr = match get_diag_attr(&input.attrs) {
         Ok(message) => message,
@@ -26,11 +31,12 @@ pub fn into_diag_derive(input: TokenStream) -> TokenStream {
             diag_ctxt,
             DiagnosticMsg::new(format!(#diagnostic_attr, #(self.#field_names),*).into(),)
         )
+        #(.with_span(self.#span_names))*
     };
 
     let gen = quote! {
         impl<'a> IntoDiagnostic<'a> for #name {
-            fn into_diag(&self, diag_ctxt: &'a DiagnosticCtxt) -> Diagnostic<'a> {
+            fn into_diag(self, diag_ctxt: &'a DiagnosticCtxt) -> Diagnostic<'a> {
                 #implementation
             }
         }
@@ -62,11 +68,9 @@ fn get_fields(input: Data) -> Result<FieldsNamed, Error> {
                 "This macro only support `struct`s with named fields",
             )),
         },
-        _ => {
-            return Err(Error::new(
-                Span::call_site(),
-                "This macro should only use with `struct`s",
-            ))
-        }
+        _ => Err(Error::new(
+            Span::call_site(),
+            "This macro should only use with `struct`s",
+        )),
     }
 }
@@ -2,22 +2,64 @@ pub mod token;
 
 use std::collections::VecDeque;
 
-use token::{BinOpKind, LiteralKind, Token};
+use crate::span::Span;
+use token::{BinOpKind, LiteralKind, Token, UnOpKind};
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub enum Ast {
-    Stmt(Stmt),
+    Stmts(Vec<Stmt>),
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub enum Stmt {
     Expr(Expr),
+    Let(Let),
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
-pub enum Expr {
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum ExprKind {
     Lit(Lit),
     BinOp(BinOp),
+    UnOp(UnOp),
+    Ident(Ident),
+    Call(Call),
+    Cond(Cond),
+    // Placeholder for an expression that failed to parse, so we can continue parsing
+    Err,
+}
+
+// `let name = expr`
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct Let {
+    pub ident: Ident,
+    pub expr: Expr,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct Ident {
+    pub name: String,
+    pub span: Span,
+}
+
+// `name(arg, ...)`
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct Call {
+    pub ident: Ident,
+    pub args: Vec<Expr>,
+}
+
+// `cond ? if_true : if_false`
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct Cond {
+    pub cond: Box<Expr>,
+    pub if_true: Box<Expr>,
+    pub if_false: Box<Expr>,
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
@@ -27,6 +69,12 @@ pub struct BinOp {
     pub right: Box<Expr>,
 }
 
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct UnOp {
+    pub kind: UnOpKind,
+    pub expr: Box<Expr>,
+}
+
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub struct Lit {
     pub kind: LiteralKind,
@@ -35,6 +83,12 @@ pub struct Lit {
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub struct TokenStream(VecDeque<Token>);
 
+impl Expr {
+    pub fn new(kind: ExprKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
 impl BinOp {
     pub fn new(left: Expr, kind: BinOpKind, right: Expr) -> Self {
         Self {
@@ -45,6 +99,43 @@ impl BinOp {
     }
 }
 
+impl UnOp {
+    pub fn new(kind: UnOpKind, expr: Expr) -> Self {
+        Self {
+            kind,
+            expr: Box::new(expr),
+        }
+    }
+}
+
+impl Let {
+    pub fn new(ident: Ident, expr: Expr) -> Self {
+        Self { ident, expr }
+    }
+}
+
+impl Ident {
+    pub fn new(name: String, span: Span) -> Self {
+        Self { name, span }
+    }
+}
+
+impl Call {
+    pub fn new(ident: Ident, args: Vec<Expr>) -> Self {
+        Self { ident, args }
+    }
+}
+
+impl Cond {
+    pub fn new(cond: Expr, if_true: Expr, if_false: Expr) -> Self {
+        Self {
+            cond: Box::new(cond),
+            if_true: Box::new(if_true),
+            if_false: Box::new(if_false),
+        }
+    }
+}
+
 impl Lit {
     pub fn new(kind: LiteralKind) -> Self {
         Self { kind }
@@ -55,6 +146,10 @@ impl TokenStream {
     pub fn new(stream: VecDeque<Token>) -> Self {
         Self(stream)
     }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Token> {
+        self.0.iter_mut()
+    }
 }
 
 impl Iterator for TokenStream {
@@ -1,9 +1,29 @@
+use crate::span::Span;
+
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
-pub enum Token {
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum TokenKind {
     Lit { kind: LiteralKind },
+    Ident { name: String },
     BinOp(BinOpKind),
     OpenParen,
     CloseParen,
+    Eq,
+    Semi,
+    Comma,
+    // `~` and `!`, unary operators that can't be binary
+    Tilde,
+    Bang,
+    // `?` and `:` of `cond ? a : b`
+    Question,
+    Colon,
+    // `let` keyword
+    Let,
     Eof,
     Unknown { content: String },
     // init token (for example, for Parser::new())
@@ -14,6 +34,20 @@ pub enum Token {
 pub enum LiteralKind {
     Int { val: String },
     Float { val: String },
+    // `true` or `false`
+    Bool { val: bool },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UnOpKind {
+    // `-expr`
+    Neg,
+    // `+expr`
+    Pos,
+    // `~expr`
+    BitNot,
+    // `!expr`
+    Not,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -23,4 +57,24 @@ pub enum BinOpKind {
     Mul,
     Div,
     Mod,
+    Pow,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+impl Token {
+    pub fn new(kind: TokenKind, span: Span) -> Self {
+        Self { kind, span }
+    }
 }
@@ -1,67 +1,351 @@
-use std::str::FromStr as _;
+use std::{collections::HashSet, fmt::Display, str::FromStr};
 
-use crate::errors::diagnostic::{Diagnostic, DiagnosticCtxt};
-use ast::{Ast, BinOp, BinOpKind, Expr, Lit, LiteralKind, Stmt};
+use bigdecimal::BigDecimal;
+use num_traits::Num;
+
+use crate::{
+    compile::NumMode,
+    errors::diagnostic::{Diagnostic, DiagnosticCtxt},
+    helpers::stack::ensure_sufficient_stack,
+    span::Span,
+};
+use ast::{
+    Ast, BinOp, BinOpKind, Call, Cond, Expr, ExprKind, Ident, Let, Lit, LiteralKind, Stmt, UnOp,
+    UnOpKind,
+};
 
 pub mod ast;
 pub mod errors;
 
+/// Predefined constants. Their names are reserved, so they can't be redefined by `let`.
+/// Values are strings, so they are precise enough for both native and big numbers.
+pub const CONSTANTS: &[(&str, &str)] = &[
+    ("pi", "3.14159265358979323846264338327950288419716939937510"),
+    ("e", "2.71828182845904523536028747135266249775724709369995"),
+    ("inf", "inf"),
+];
+
 pub struct Lower<'a> {
     diag_ctxt: &'a DiagnosticCtxt,
+    // Names of variables that are defined before the current statement
+    bound: HashSet<String>,
+    num_mode: NumMode,
 }
 
 impl<'a> Lower<'a> {
-    pub fn new(diag_ctxt: &'a DiagnosticCtxt) -> Self {
-        Self { diag_ctxt }
+    /// `bound` are the variables that are defined outside of the lowered ast
+    /// (for example, by previous lines in REPL)
+    pub fn new(diag_ctxt: &'a DiagnosticCtxt, bound: HashSet<String>, num_mode: NumMode) -> Self {
+        Self {
+            diag_ctxt,
+            bound,
+            num_mode,
+        }
     }
 
-    pub fn lower(&self, ast: crate::ast::Ast) -> Result<Ast, Diagnostic<'a>> {
+    /// Lowers all statements even if some of them are invalid, so all errors are emitted.
+    /// Returns the first of them.
+    pub fn lower(&mut self, ast: crate::ast::Ast) -> Result<Ast, Diagnostic<'a>> {
         match ast {
-            crate::ast::Ast::Stmt(stmt) => Ok(Ast::Stmt(self.lower_stmt(stmt)?)),
+            crate::ast::Ast::Stmts(stmts) => {
+                let mut lowered = Vec::with_capacity(stmts.len());
+                let mut first_err = None;
+
+                for stmt in stmts {
+                    match self.lower_stmt(stmt) {
+                        Ok(stmt) => lowered.push(stmt),
+                        Err(err) => {
+                            first_err.get_or_insert(err);
+                        }
+                    }
+                }
+
+                match first_err {
+                    Some(err) => Err(err),
+                    None => Ok(Ast::Stmts(lowered)),
+                }
+            }
         }
     }
 
-    fn lower_stmt(&self, stmt: crate::ast::Stmt) -> Result<Stmt, Diagnostic<'a>> {
+    fn lower_stmt(&mut self, stmt: crate::ast::Stmt) -> Result<Stmt, Diagnostic<'a>> {
         match stmt {
             crate::ast::Stmt::Expr(expr) => Ok(Stmt::Expr(self.lower_expr(expr)?)),
+            crate::ast::Stmt::Let(crate::ast::Let { ident, expr }) => {
+                // Variable isn't visible in its own definition
+                let expr = self.lower_expr(expr);
+
+                if lookup_constant(&ident.name).is_some() {
+                    return Err(self
+                        .diag_ctxt
+                        .handle()
+                        .emit_err(errors::RedefinedConstant::new(ident.name, ident.span)));
+                }
+                // Define it even if its expression is invalid, so we dont report its uses
+                self.bound.insert(ident.name.clone());
+
+                Ok(Stmt::Let(Let {
+                    ident: self.lower_ident(ident),
+                    expr: expr?,
+                }))
+            }
         }
     }
 
-    fn lower_expr(&self, expr: crate::ast::Expr) -> Result<Expr, Diagnostic<'a>> {
-        match expr {
-            crate::ast::Expr::Lit(lit) => Ok(Expr::Lit(self.lower_lit(lit)?)),
-            crate::ast::Expr::BinOp(binop) => Ok(Expr::BinOp(self.lower_binop(binop)?)),
+    fn lower_expr(
+        &mut self,
+        crate::ast::Expr { kind, span }: crate::ast::Expr,
+    ) -> Result<Expr, Diagnostic<'a>> {
+        ensure_sufficient_stack(|| {
+            let kind = match kind {
+                crate::ast::ExprKind::Lit(lit) => ExprKind::Lit(self.lower_lit(lit, span)?),
+                crate::ast::ExprKind::BinOp(binop) => ExprKind::BinOp(self.lower_binop(binop)?),
+                crate::ast::ExprKind::UnOp(unop) => ExprKind::UnOp(self.lower_unop(unop)?),
+                crate::ast::ExprKind::Ident(ident) => self.lower_word(ident)?,
+                crate::ast::ExprKind::Call(call) => ExprKind::Call(self.lower_call(call)?),
+                crate::ast::ExprKind::Cond(cond) => ExprKind::Cond(self.lower_cond(cond)?),
+                crate::ast::ExprKind::Err => {
+                    unreachable!(
+                        "Parser never returns an ast with expressions that failed to parse"
+                    )
+                }
+            };
+
+            Ok(Expr { kind, span })
+        })
+    }
+
+    // Bare word is either a variable or a constant
+    fn lower_word(&self, ident: crate::ast::Ident) -> Result<ExprKind, Diagnostic<'a>> {
+        if self.bound.contains(&ident.name) {
+            return Ok(ExprKind::Ident(self.lower_ident(ident)));
+        }
+
+        match lookup_constant(&ident.name) {
+            Some(val) => Ok(ExprKind::Lit(
+                self.lower_constant(ident.name, val, ident.span)?,
+            )),
+            None => Err(self
+                .diag_ctxt
+                .handle()
+                .emit_err(errors::UnknownIdentifier::new(ident.name, ident.span))),
         }
     }
 
+    fn lower_ident(&self, crate::ast::Ident { name, span }: crate::ast::Ident) -> Ident {
+        Ident { name, span }
+    }
+
+    fn lower_call(
+        &mut self,
+        crate::ast::Call { ident, args }: crate::ast::Call,
+    ) -> Result<Call, Diagnostic<'a>> {
+        // Lower all arguments before returning an error to report errors in all of them
+        let args: Vec<_> = args.into_iter().map(|arg| self.lower_expr(arg)).collect();
+
+        Ok(Call {
+            ident: self.lower_ident(ident),
+            args: args.into_iter().collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn lower_cond(
+        &mut self,
+        crate::ast::Cond {
+            cond,
+            if_true,
+            if_false,
+        }: crate::ast::Cond,
+    ) -> Result<Cond, Diagnostic<'a>> {
+        // Lower all parts before returning an error to report errors in all of them
+        let (cond, if_true, if_false) = (
+            self.lower_expr(*cond),
+            self.lower_expr(*if_true),
+            self.lower_expr(*if_false),
+        );
+
+        Ok(Cond {
+            cond: Box::new(cond?),
+            if_true: Box::new(if_true?),
+            if_false: Box::new(if_false?),
+        })
+    }
+
     fn lower_binop(
-        &self,
+        &mut self,
         crate::ast::BinOp {
             left,
             kind: op,
             right,
         }: crate::ast::BinOp,
     ) -> Result<BinOp, Diagnostic<'a>> {
+        // Lower both sides before returning an error to report errors in both of them
+        let (lhs, rhs) = (self.lower_expr(*left), self.lower_expr(*right));
+
         Ok(BinOp {
-            lhs: Box::new(self.lower_expr(*left)?),
-            rhs: Box::new(self.lower_expr(*right)?),
+            lhs: Box::new(lhs?),
+            rhs: Box::new(rhs?),
             op: self.lower_op(op),
         })
     }
 
-    fn lower_lit(&self, lit: crate::ast::Lit) -> Result<Lit, Diagnostic<'a>> {
-        match lit.kind {
-            crate::ast::token::LiteralKind::Int { val } => Ok(Lit {
-                kind: LiteralKind::Int {
-                    val: self.lit_to_i32(val)?,
-                },
-            }),
-            crate::ast::token::LiteralKind::Float { val } => Ok(Lit {
-                kind: LiteralKind::Float {
-                    val: self.lit_to_f32(val)?,
-                },
-            }),
+    fn lower_unop(
+        &mut self,
+        crate::ast::UnOp { kind: op, expr }: crate::ast::UnOp,
+    ) -> Result<UnOp, Diagnostic<'a>> {
+        Ok(UnOp {
+            expr: Box::new(self.lower_expr(*expr)?),
+            op: self.lower_unop_kind(op),
+        })
+    }
+
+    fn lower_lit(&self, lit: crate::ast::Lit, span: Span) -> Result<Lit, Diagnostic<'a>> {
+        let kind = match lit.kind {
+            crate::ast::token::LiteralKind::Int { val } => {
+                let (digits, radix) = self.split_int_literal(&val, span)?;
+
+                match self.num_mode {
+                    NumMode::Native => LiteralKind::Int {
+                        val: self.parse_int(val, &digits, radix, span)?,
+                    },
+                    NumMode::Big => LiteralKind::BigInt {
+                        val: self.parse_int(val, &digits, radix, span)?,
+                    },
+                }
+            }
+            crate::ast::token::LiteralKind::Bool { val } => LiteralKind::Bool { val },
+            crate::ast::token::LiteralKind::Float { val } => {
+                let float = self.clean_float_literal(&val, span)?;
+
+                match self.num_mode {
+                    NumMode::Native => LiteralKind::Float {
+                        val: self.parse_native_float(val, &float, span)?,
+                    },
+                    NumMode::Big => LiteralKind::BigDecimal {
+                        val: self.parse_float(val, &float, span)?,
+                    },
+                }
+            }
+        };
+
+        Ok(Lit { kind })
+    }
+
+    // Removes the prefix and underscores of the integer literal and checks its digits,
+    // so `0x1_F` is `("1F", 16)`
+    fn split_int_literal(&self, lit: &str, span: Span) -> Result<(String, u32), Diagnostic<'a>> {
+        let (radix, base, prefix_len) = match literal_prefix(lit) {
+            Some((radix, base)) => (radix, base, 2),
+            None => (10, "decimal", 0),
+        };
+        let mut digits = String::new();
+
+        for (i, ch) in lit.char_indices().skip(prefix_len) {
+            match ch {
+                '_' => {}
+                ch if ch.is_digit(radix) => digits.push(ch),
+                _ => {
+                    return Err(self.diag_ctxt.handle().emit_err(errors::InvalidDigit::new(
+                        ch,
+                        base.to_string(),
+                        char_span(span, i, ch),
+                    )))
+                }
+            }
         }
+
+        // Only a prefix can be without digits, because a decimal literal starts with a digit
+        if digits.is_empty() {
+            let err = self
+                .diag_ctxt
+                .handle()
+                .struct_err(errors::EmptyNumberLiteral::new(
+                    base.to_string(),
+                    lit.to_string(),
+                    span,
+                ))
+                .with_help(format!(
+                    "add digits after the prefix, for example, `{lit}1`"
+                ));
+            err.emit();
+
+            return Err(err);
+        }
+
+        Ok((digits, radix))
+    }
+
+    // Removes underscores of the float literal and checks its digits and exponent,
+    // so `1_000.5e-3` is `1000.5e-3`
+    fn clean_float_literal(&self, lit: &str, span: Span) -> Result<String, Diagnostic<'a>> {
+        let handle = self.diag_ctxt.handle();
+        if let Some((_, base)) = literal_prefix(lit) {
+            return Err(handle.emit_err(errors::FractionalPrefixedLiteral::new(
+                base.to_string(),
+                lit.to_string(),
+                span,
+            )));
+        }
+
+        let mut float = String::new();
+        // Offset of `e` in the literal
+        let mut exp = None;
+
+        for (i, ch) in lit.char_indices() {
+            match ch {
+                '_' => {}
+                // Literals with several points are reported by `check_token_stream()`
+                // before lowering, and lexer puts signs only after `e`
+                '0'..='9' | '+' | '-' => float.push(ch),
+                '.' if exp.is_none() => float.push(ch),
+                'e' | 'E' if exp.is_none() => {
+                    exp = Some(i);
+                    // `1.e3` is the same as `1.0e3`
+                    if float.ends_with('.') {
+                        float.push('0');
+                    }
+                    float.push('e');
+                }
+                _ => {
+                    return Err(handle.emit_err(errors::InvalidDigit::new(
+                        ch,
+                        "decimal".to_string(),
+                        char_span(span, i, ch),
+                    )))
+                }
+            }
+        }
+
+        if let Some(exp) = exp {
+            if !float.ends_with(|ch: char| ch.is_ascii_digit()) {
+                return Err(handle.emit_err(errors::EmptyExponent::new(
+                    lit.to_string(),
+                    Span::new(span.lo + exp, span.hi),
+                )));
+            }
+        }
+
+        Ok(float)
+    }
+
+    fn lower_constant(&self, name: String, val: &str, span: Span) -> Result<Lit, Diagnostic<'a>> {
+        let kind = match self.num_mode {
+            NumMode::Native => LiteralKind::Float {
+                val: val.parse().expect("Constant is not a valid float"),
+            },
+            NumMode::Big => match BigDecimal::from_str(val) {
+                Ok(val) => LiteralKind::BigDecimal { val },
+                // `inf` is not a number that we can calculate exactly
+                Err(_) => {
+                    return Err(self
+                        .diag_ctxt
+                        .handle()
+                        .emit_err(errors::UnsupportedBigConstant::new(name, span)))
+                }
+            },
+        };
+
+        Ok(Lit { kind })
     }
 
     fn lower_op(&self, op: crate::ast::token::BinOpKind) -> BinOpKind {
@@ -71,26 +355,122 @@ impl<'a> Lower<'a> {
             crate::ast::token::BinOpKind::Mul => BinOpKind::Mul,
             crate::ast::token::BinOpKind::Div => BinOpKind::Div,
             crate::ast::token::BinOpKind::Mod => BinOpKind::Mod,
+            crate::ast::token::BinOpKind::Pow => BinOpKind::Pow,
+            crate::ast::token::BinOpKind::BitAnd => BinOpKind::BitAnd,
+            crate::ast::token::BinOpKind::BitOr => BinOpKind::BitOr,
+            crate::ast::token::BinOpKind::BitXor => BinOpKind::BitXor,
+            crate::ast::token::BinOpKind::Shl => BinOpKind::Shl,
+            crate::ast::token::BinOpKind::Shr => BinOpKind::Shr,
+            crate::ast::token::BinOpKind::Lt => BinOpKind::Lt,
+            crate::ast::token::BinOpKind::Le => BinOpKind::Le,
+            crate::ast::token::BinOpKind::Gt => BinOpKind::Gt,
+            crate::ast::token::BinOpKind::Ge => BinOpKind::Ge,
+            crate::ast::token::BinOpKind::Eq => BinOpKind::Eq,
+            crate::ast::token::BinOpKind::Ne => BinOpKind::Ne,
+            crate::ast::token::BinOpKind::And => BinOpKind::And,
+            crate::ast::token::BinOpKind::Or => BinOpKind::Or,
+        }
+    }
+
+    fn lower_unop_kind(&self, op: crate::ast::token::UnOpKind) -> UnOpKind {
+        match op {
+            crate::ast::token::UnOpKind::Neg => UnOpKind::Neg,
+            crate::ast::token::UnOpKind::Pos => UnOpKind::Pos,
+            crate::ast::token::UnOpKind::BitNot => UnOpKind::BitNot,
+            crate::ast::token::UnOpKind::Not => UnOpKind::Not,
         }
     }
 
-    fn lit_to_i32(&self, maybe_int: String) -> Result<i32, Diagnostic<'a>> {
-        match i32::from_str_radix(maybe_int.as_str(), 10) {
+    // `literal` is the source of the number, so errors show it as the user wrote it
+    fn parse_int<T: Num<FromStrRadixErr = E>, E: Display>(
+        &self,
+        literal: String,
+        digits: &str,
+        radix: u32,
+        span: Span,
+    ) -> Result<T, Diagnostic<'a>> {
+        match T::from_str_radix(digits, radix) {
             Ok(int_num) => Ok(int_num),
-            Err(err) => Err(self
-                .diag_ctxt
-                .handle()
-                .emit_err(errors::ParseIntError::new(maybe_int, err.to_string()))),
+            Err(err) => {
+                let err = self
+                    .diag_ctxt
+                    .handle()
+                    .struct_err(errors::ParseIntError::new(literal, err.to_string(), span));
+                // Only native integers can be too large
+                let err = match self.num_mode {
+                    NumMode::Native => {
+                        err.with_help("use `--numbers=big` for arbitrary-precision integers")
+                    }
+                    NumMode::Big => err,
+                };
+                err.emit();
+
+                Err(err)
+            }
         }
     }
 
-    fn lit_to_f32(&self, maybe_float: String) -> Result<f32, Diagnostic<'a>> {
-        match f32::from_str(maybe_float.as_str()) {
+    // `f64` parses too large literals like `1e400` as `inf`, but it's not what the user wrote,
+    // so they are reported like too large integers
+    fn parse_native_float(
+        &self,
+        literal: String,
+        float: &str,
+        span: Span,
+    ) -> Result<f64, Diagnostic<'a>> {
+        let float_num: f64 = self.parse_float(literal.clone(), float, span)?;
+        if float_num.is_finite() {
+            return Ok(float_num);
+        }
+
+        let err = self
+            .diag_ctxt
+            .handle()
+            .struct_err(errors::ParseFloatError::new(
+                literal,
+                "number too large to fit in target type".to_string(),
+                span,
+            ))
+            .with_help("use `--numbers=big` for arbitrary-precision floats");
+        err.emit();
+
+        Err(err)
+    }
+
+    fn parse_float<T: FromStr<Err = E>, E: Display>(
+        &self,
+        literal: String,
+        float: &str,
+        span: Span,
+    ) -> Result<T, Diagnostic<'a>> {
+        match float.parse::<T>() {
             Ok(float_num) => Ok(float_num),
             Err(err) => Err(self
                 .diag_ctxt
                 .handle()
-                .emit_err(errors::ParseFloatError::new(maybe_float, err.to_string()))),
+                .emit_err(errors::ParseFloatError::new(literal, err.to_string(), span))),
         }
     }
 }
+
+// Radix and name of the number literal with the prefix `0x`, `0o` or `0b`
+fn literal_prefix(lit: &str) -> Option<(u32, &'static str)> {
+    match lit.get(..2)? {
+        "0x" => Some((16, "hexadecimal")),
+        "0o" => Some((8, "octal")),
+        "0b" => Some((2, "binary")),
+        _ => None,
+    }
+}
+
+// Span of the char at the byte offset `pos` of the literal with the span `lit`
+fn char_span(lit: Span, pos: usize, ch: char) -> Span {
+    Span::new(lit.lo + pos, lit.lo + pos + ch.len_utf8())
+}
+
+fn lookup_constant(name: &str) -> Option<&'static str> {
+    CONSTANTS
+        .iter()
+        .find(|(constant, _)| *constant == name)
+        .map(|(_, val)| *val)
+}
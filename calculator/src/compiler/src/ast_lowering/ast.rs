@@ -1,17 +1,88 @@
+use bigdecimal::BigDecimal;
+use num_bigint::BigInt;
+
+use crate::{helpers::stack::ensure_sufficient_stack, span::Span};
+
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub enum Ast {
-    Stmt(Stmt),
+    Stmts(Vec<Stmt>),
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub enum Stmt {
     Expr(Expr),
+    Let(Let),
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
-pub enum Expr {
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum ExprKind {
     Lit(Lit),
     BinOp(BinOp),
+    UnOp(UnOp),
+    Ident(Ident),
+    Call(Call),
+    Cond(Cond),
+}
+
+impl Ast {
+    /// Drops all statements without recursion. The default drop of `Box` is recursive,
+    /// so deeply nested expressions would overflow the stack even after they were calculated.
+    pub fn drop_iteratively(&mut self) {
+        let Ast::Stmts(stmts) = self;
+        let mut exprs: Vec<Expr> = stmts
+            .drain(..)
+            .map(|stmt| match stmt {
+                Stmt::Expr(expr) | Stmt::Let(Let { expr, .. }) => expr,
+            })
+            .collect();
+
+        // Children are moved out before their parent is dropped
+        while let Some(expr) = exprs.pop() {
+            match expr.kind {
+                ExprKind::BinOp(BinOp { lhs, rhs, .. }) => exprs.extend([*lhs, *rhs]),
+                ExprKind::UnOp(UnOp { expr, .. }) => exprs.push(*expr),
+                ExprKind::Call(Call { args, .. }) => exprs.extend(args),
+                ExprKind::Cond(Cond {
+                    cond,
+                    if_true,
+                    if_false,
+                }) => exprs.extend([*cond, *if_true, *if_false]),
+                ExprKind::Lit(_) | ExprKind::Ident(_) => {}
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct Let {
+    pub ident: Ident,
+    pub expr: Expr,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Ident {
+    pub name: String,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct Call {
+    pub ident: Ident,
+    pub args: Vec<Expr>,
+}
+
+// `cond ? if_true : if_false`, only one of the branches is calculated
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct Cond {
+    pub cond: Box<Expr>,
+    pub if_true: Box<Expr>,
+    pub if_false: Box<Expr>,
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
@@ -21,6 +92,22 @@ pub struct BinOp {
     pub op: BinOpKind,
 }
 
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct UnOp {
+    pub expr: Box<Expr>,
+    pub op: UnOpKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UnOpKind {
+    Neg,
+    Pos,
+    // Bitwise not, `~expr`
+    BitNot,
+    // Logical not, `!expr`
+    Not,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum BinOpKind {
     Add,
@@ -28,15 +115,282 @@ pub enum BinOpKind {
     Mul,
     Div,
     Mod,
+    Pow,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+impl BinOpKind {
+    /// Operators that compare two values and return a boolean
+    pub fn is_comparison(self) -> bool {
+        matches!(
+            self,
+            BinOpKind::Lt
+                | BinOpKind::Le
+                | BinOpKind::Gt
+                | BinOpKind::Ge
+                | BinOpKind::Eq
+                | BinOpKind::Ne
+        )
+    }
+
+    /// Operators that can be applied only to integers
+    pub fn is_bitwise(self) -> bool {
+        matches!(
+            self,
+            BinOpKind::BitAnd
+                | BinOpKind::BitOr
+                | BinOpKind::BitXor
+                | BinOpKind::Shl
+                | BinOpKind::Shr
+        )
+    }
 }
 
-#[derive(Clone, Debug, PartialEq, PartialOrd, Copy)]
+impl std::fmt::Display for BinOpKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self {
+            BinOpKind::Add => "+",
+            BinOpKind::Sub => "-",
+            BinOpKind::Mul => "*",
+            BinOpKind::Div => "/",
+            BinOpKind::Mod => "%",
+            BinOpKind::Pow => "**",
+            BinOpKind::BitAnd => "&",
+            BinOpKind::BitOr => "|",
+            BinOpKind::BitXor => "^",
+            BinOpKind::Shl => "<<",
+            BinOpKind::Shr => ">>",
+            BinOpKind::Lt => "<",
+            BinOpKind::Le => "<=",
+            BinOpKind::Gt => ">",
+            BinOpKind::Ge => ">=",
+            BinOpKind::Eq => "==",
+            BinOpKind::Ne => "!=",
+            BinOpKind::And => "&&",
+            BinOpKind::Or => "||",
+        };
+
+        write!(f, "{op}")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub struct Lit {
     pub kind: LiteralKind,
 }
 
-#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+// `BigInt` and `BigDecimal` are used instead of `Int` and `Float` in the big number mode
+#[derive(Clone, PartialEq, PartialOrd)]
 pub enum LiteralKind {
-    Int { val: i32 },
-    Float { val: f32 },
+    Int { val: i64 },
+    Float { val: f64 },
+    BigInt { val: BigInt },
+    BigDecimal { val: BigDecimal },
+    Bool { val: bool },
+}
+
+impl std::fmt::Display for LiteralKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LiteralKind::Int { val } => write!(f, "{val}"),
+            LiteralKind::Float { val } => write!(f, "{val}"),
+            LiteralKind::BigInt { val } => write!(f, "{val}"),
+            LiteralKind::BigDecimal { val } => write!(f, "{val}"),
+            LiteralKind::Bool { val } => write!(f, "{val}"),
+        }
+    }
+}
+
+// `Debug` of `BigDecimal` shows its internal representation, so we show its value instead
+impl std::fmt::Debug for LiteralKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LiteralKind::Int { val } => f.debug_struct("Int").field("val", val).finish(),
+            LiteralKind::Float { val } => f.debug_struct("Float").field("val", val).finish(),
+            LiteralKind::BigInt { val } => f.debug_struct("BigInt").field("val", val).finish(),
+            LiteralKind::BigDecimal { val } => f
+                .debug_struct("BigDecimal")
+                .field("val", &format_args!("{val}"))
+                .finish(),
+            LiteralKind::Bool { val } => f.debug_struct("Bool").field("val", val).finish(),
+        }
+    }
+}
+
+// Precedence of operators, from the lowest to the highest, as in the parser
+const PREC_COND: u8 = 0;
+const PREC_UNARY: u8 = 10;
+const PREC_POW: u8 = 11;
+// Literals, variables and calls never need parentheses
+const PREC_ATOM: u8 = 12;
+
+impl BinOpKind {
+    fn precedence(self) -> u8 {
+        match self {
+            BinOpKind::Or => 1,
+            BinOpKind::And => 2,
+            BinOpKind::Lt
+            | BinOpKind::Le
+            | BinOpKind::Gt
+            | BinOpKind::Ge
+            | BinOpKind::Eq
+            | BinOpKind::Ne => 3,
+            BinOpKind::BitOr => 4,
+            BinOpKind::BitXor => 5,
+            BinOpKind::BitAnd => 6,
+            BinOpKind::Shl | BinOpKind::Shr => 7,
+            BinOpKind::Add | BinOpKind::Sub => 8,
+            BinOpKind::Mul | BinOpKind::Div | BinOpKind::Mod => 9,
+            BinOpKind::Pow => PREC_POW,
+        }
+    }
+}
+
+impl std::fmt::Display for UnOpKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self {
+            UnOpKind::Neg => "-",
+            UnOpKind::Pos => "+",
+            UnOpKind::BitNot => "~",
+            UnOpKind::Not => "!",
+        };
+
+        write!(f, "{op}")
+    }
+}
+
+/// Shows the program as source code that is parsed back to the same program.
+/// Only necessary parentheses are added, so `(1 + 2) * 3` stays as is, but `(1 * 2) + 3`
+/// is `1 * 2 + 3`.
+impl std::fmt::Display for Ast {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ast::Stmts(stmts) => {
+                for (i, stmt) in stmts.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    match stmt {
+                        Stmt::Expr(expr) => write!(f, "{expr}")?,
+                        Stmt::Let(Let { ident, expr }) => write!(f, "let {} = {expr}", ident.name)?,
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        ensure_sufficient_stack(|| self.fmt_expr(f))
+    }
+}
+
+impl Expr {
+    fn fmt_expr(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ExprKind::Lit(Lit { kind }) => write!(f, "{}", kind.to_source()),
+            ExprKind::Ident(ident) => write!(f, "{}", ident.name),
+            ExprKind::Call(Call { ident, args }) => {
+                write!(f, "{}(", ident.name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{arg}")?;
+                }
+                write!(f, ")")
+            }
+            ExprKind::UnOp(UnOp { expr, op }) => {
+                write!(f, "{op}")?;
+                expr.fmt_operand(f, PREC_UNARY)
+            }
+            // Power is right associative and binds tighter than unary operators,
+            // so `(-2) ** 3 ** 2` keeps only its parentheses
+            ExprKind::BinOp(BinOp { lhs, rhs, op }) if *op == BinOpKind::Pow => {
+                lhs.fmt_operand(f, PREC_ATOM)?;
+                write!(f, " {op} ")?;
+                rhs.fmt_operand(f, PREC_UNARY)
+            }
+            ExprKind::BinOp(BinOp { lhs, rhs, op }) => {
+                lhs.fmt_operand(f, op.precedence())?;
+                write!(f, " {op} ")?;
+                rhs.fmt_operand(f, op.precedence() + 1)
+            }
+            // Conditional expression is right associative
+            ExprKind::Cond(Cond {
+                cond,
+                if_true,
+                if_false,
+            }) => {
+                cond.fmt_operand(f, PREC_COND + 1)?;
+                write!(f, " ? {if_true} : {if_false}")
+            }
+        }
+    }
+}
+
+impl Expr {
+    fn precedence(&self) -> u8 {
+        match &self.kind {
+            ExprKind::Lit(Lit { kind }) if kind.to_source().starts_with('-') => PREC_UNARY,
+            ExprKind::Lit(_) | ExprKind::Ident(_) | ExprKind::Call(_) => PREC_ATOM,
+            ExprKind::UnOp(_) => PREC_UNARY,
+            ExprKind::BinOp(BinOp { op, .. }) => op.precedence(),
+            ExprKind::Cond(_) => PREC_COND,
+        }
+    }
+
+    // Shows the expression in parentheses if it binds weaker than `min_prec`
+    fn fmt_operand(&self, f: &mut std::fmt::Formatter<'_>, min_prec: u8) -> std::fmt::Result {
+        if self.precedence() < min_prec {
+            write!(f, "({self})")
+        } else {
+            write!(f, "{self}")
+        }
+    }
+}
+
+impl LiteralKind {
+    /// Shows the value as a literal, so floats always have a point or an exponent
+    /// and are not parsed back as integers
+    pub fn to_source(&self) -> String {
+        match self {
+            LiteralKind::Float { val } => format!("{val:?}"),
+            LiteralKind::BigDecimal { val } => {
+                let val = val.to_string();
+                if val.contains(['.', 'e', 'E']) {
+                    val
+                } else {
+                    format!("{val}.0")
+                }
+            }
+            _ => self.to_string(),
+        }
+    }
+
+    /// Name of the type of the value, as it's shown in diagnostics
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            LiteralKind::Int { .. } => "Int",
+            LiteralKind::Float { .. } => "Float",
+            LiteralKind::BigInt { .. } => "BigInt",
+            LiteralKind::BigDecimal { .. } => "BigDecimal",
+            LiteralKind::Bool { .. } => "Bool",
+        }
+    }
 }
@@ -1,4 +1,5 @@
 use crate::errors::diagnostic::{Diagnostic, DiagnosticCtxt, DiagnosticMsg, IntoDiagnostic};
+use crate::span::Span;
 use calculator_macros::IntoDiagnostic;
 
 #[derive(IntoDiagnostic)]
@@ -6,11 +7,13 @@ use calculator_macros::IntoDiagnostic;
 pub struct ParseFloatError {
     float: String,
     msg: String,
+    #[primary_span]
+    span: Span,
 }
 
 impl ParseFloatError {
-    pub fn new(float: String, msg: String) -> Self {
-        Self { float, msg }
+    pub fn new(float: String, msg: String, span: Span) -> Self {
+        Self { float, msg, span }
     }
 }
 
@@ -19,10 +22,121 @@ impl ParseFloatError {
 pub struct ParseIntError {
     int: String,
     msg: String,
+    #[primary_span]
+    span: Span,
 }
 
 impl ParseIntError {
-    pub fn new(int: String, msg: String) -> Self {
-        Self { int, msg }
+    pub fn new(int: String, msg: String, span: Span) -> Self {
+        Self { int, msg, span }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("cannot find variable `{}` in this scope")]
+pub struct UnknownIdentifier {
+    name: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl UnknownIdentifier {
+    pub fn new(name: String, span: Span) -> Self {
+        Self { name, span }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("cannot redefine constant `{}`")]
+pub struct RedefinedConstant {
+    name: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl RedefinedConstant {
+    pub fn new(name: String, span: Span) -> Self {
+        Self { name, span }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("constant `{}` is not supported with big numbers")]
+pub struct UnsupportedBigConstant {
+    name: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl UnsupportedBigConstant {
+    pub fn new(name: String, span: Span) -> Self {
+        Self { name, span }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("invalid digit `{}` in {} number literal")]
+pub struct InvalidDigit {
+    digit: char,
+    base: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl InvalidDigit {
+    pub fn new(digit: char, base: String, span: Span) -> Self {
+        Self { digit, base, span }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("{} number literal `{}` has no digits")]
+pub struct EmptyNumberLiteral {
+    base: String,
+    literal: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl EmptyNumberLiteral {
+    pub fn new(base: String, literal: String, span: Span) -> Self {
+        Self {
+            base,
+            literal,
+            span,
+        }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("expected at least one digit in exponent of `{}`")]
+pub struct EmptyExponent {
+    literal: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl EmptyExponent {
+    pub fn new(literal: String, span: Span) -> Self {
+        Self { literal, span }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("{} number literal `{}` can't have a fractional part")]
+pub struct FractionalPrefixedLiteral {
+    base: String,
+    literal: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl FractionalPrefixedLiteral {
+    pub fn new(base: String, literal: String, span: Span) -> Self {
+        Self {
+            base,
+            literal,
+            span,
+        }
     }
 }
@@ -1,25 +1,135 @@
-use clap::Parser;
+#[cfg(test)]
+mod tests;
 
-use crate::{errors::diagnostic::DiagnosticCtxt, program_sess::ProgramSess};
+use std::num::NonZeroUsize;
+
+use clap::{ArgGroup, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
+
+use crate::{
+    compile::{Backend, CompileOptions, Emit, IntDivision, MixingPolicy, NumMode},
+    errors::{diagnostic::DiagnosticCtxt, emitter::StdoutEmitter},
+    helpers::output::Output,
+    program_sess::{find_programs, OutputFormat, ProgramSess},
+    repl::Repl,
+};
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(name = "calc", version, about, long_about = None, subcommand_negates_reqs = true)]
+#[command(group(ArgGroup::new("programs").required(true).multiple(true).args(["paths", "path"])))]
 pub struct CliLauncher {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Programs to compile: files, directories with programs
+    /// or glob patterns like `examples/**/*.calc`
+    paths: Vec<String>,
+    /// The same as a positional path, can be repeated
+    #[arg(short, long)]
+    path: Vec<String>,
+    /// Also compile programs in subdirectories of the given directories
+    #[arg(short, long)]
+    recursive: bool,
+    /// How numbers are represented: `native` are 64-bit integers and floats,
+    /// `big` are arbitrary-precision integers and decimals with exact results
+    #[arg(long, value_enum, default_value_t = NumMode::Native, global = true)]
+    numbers: NumMode,
+    /// What happens when integers and floats are used in the same operation:
+    /// `promote` converts integers to floats with a warning, `strict` reports an error
+    /// and `silent` converts them without a warning
+    #[arg(long, value_enum, default_value_t = MixingPolicy::Promote, global = true)]
+    mixing: MixingPolicy,
+    /// Result of dividing an integer by an integer: `truncate` rounds it toward zero
+    /// (`7 / 2` is `3`), `float` keeps the fractional part (`7 / 2` is `3.5`)
+    #[arg(long, value_enum, default_value_t = IntDivision::Truncate, global = true)]
+    int_div: IntDivision,
+    /// What is printed for every program: `result` is its result, `simplified` is
+    /// the program after constant folding and simplification, `c`, `asm` and `llvm`
+    /// write the generated code next to the program
+    #[arg(long, value_enum, default_value_t = Emit::Result, global = true)]
+    emit: Emit,
+    /// How programs are calculated: `tree` walks their syntax tree, `vm` compiles them
+    /// to bytecode and runs it on a stack machine
+    #[arg(long, value_enum, default_value_t = Backend::Tree, global = true)]
+    backend: Backend,
+    /// How results and diagnostics of programs are printed: `text` for humans
+    /// or `json` with one object per program
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+    /// Compile programs on this number of threads, their output is still printed
+    /// in the order of programs
     #[arg(short, long)]
-    path: String,
+    jobs: Option<NonZeroUsize>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Read programs line by line from stdin and print their results
+    Repl,
 }
 
 impl CliLauncher {
-    pub fn launch_with_exit(diag_ctxt: DiagnosticCtxt) {
-        let path = CliLauncher::parse().path;
-        let mut psess = match ProgramSess::from_path(path.as_str(), &diag_ctxt) {
-            Ok(psess) => psess,
+    pub fn launch_with_exit() {
+        let matches = CliLauncher::command().get_matches();
+        let cli = CliLauncher::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+        let options = CompileOptions {
+            num_mode: cli.numbers,
+            mixing: cli.mixing,
+            int_div: cli.int_div,
+            emit: cli.emit,
+            backend: cli.backend,
+        };
+
+        match cli.command {
+            Some(Command::Repl) => {
+                let diag_ctxt = DiagnosticCtxt::new(Box::new(StdoutEmitter::new()));
+                if let Err(err) = Repl::new(&diag_ctxt, options).run() {
+                    println!("Failed to read program from stdin: {err}");
+                    std::process::exit(1)
+                }
+            }
+            None => {
+                // `clap` checks that at least one path is specified if there is no subcommand
+                let paths = paths_in_order(&matches);
+
+                Self::run_paths_with_exit(paths, cli.recursive, cli.format, cli.jobs, options);
+            }
+        }
+    }
+
+    fn run_paths_with_exit(
+        paths: Vec<String>,
+        recursive: bool,
+        format: OutputFormat,
+        jobs: Option<NonZeroUsize>,
+        options: CompileOptions,
+    ) {
+        let (diag_ctxt, writer) = format.diag_ctxt_and_writer(Output::Stdout);
+        let programs = match find_programs(&paths, recursive) {
+            Ok(programs) => programs,
             Err(err) => {
-                println!("Failed to get `{path}`: {err}",);
+                writer.write_error(&err.to_string());
                 std::process::exit(1)
             }
         };
+        let psess = ProgramSess::new(paths, programs, &diag_ctxt, options, writer);
 
-        psess.run_with_exit();
+        match jobs {
+            Some(jobs) => psess
+                .with_jobs(jobs, move |out| format.diag_ctxt_and_writer(out))
+                .run_with_exit(),
+            None => psess.run_with_exit(),
+        }
     }
 }
+
+// Positional paths and `-p` are collected separately by `clap`,
+// but programs must be compiled in the order of the arguments
+fn paths_in_order(matches: &ArgMatches) -> Vec<String> {
+    let mut paths: Vec<(usize, String)> = ["paths", "path"]
+        .into_iter()
+        .filter_map(|id| matches.get_many::<String>(id).zip(matches.indices_of(id)))
+        .flat_map(|(values, indices)| indices.zip(values.cloned()))
+        .collect();
+    paths.sort_by_key(|(index, _)| *index);
+
+    paths.into_iter().map(|(_, path)| path).collect()
+}
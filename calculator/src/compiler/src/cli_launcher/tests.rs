@@ -0,0 +1,15 @@
+use clap::CommandFactory;
+
+use super::{paths_in_order, CliLauncher};
+
+#[test]
+fn test_paths_keep_argument_order() {
+    let matches = CliLauncher::command()
+        .try_get_matches_from(["calc", "a.calc", "-p", "b.calc", "c.calc", "--path=d.calc"])
+        .unwrap();
+
+    assert_eq!(
+        paths_in_order(&matches),
+        ["a.calc", "b.calc", "c.calc", "d.calc"]
+    );
+}
@@ -1,8 +1,12 @@
 mod calculate;
+mod codegen;
+mod options;
 
-pub use calculate::Program;
+pub use calculate::{Env, Program, MAX_BIG_BITS};
+pub use codegen::{Codegen, Target};
+pub use options::{Backend, CompileOptions, Emit, IntDivision, MixingPolicy, NumMode};
 
-pub const SOURCE_FILE_EXTENSION: &'static str = "calc";
+pub const SOURCE_FILE_EXTENSION: &str = "calc";
 
 pub trait Compile {
     type Ret;
@@ -1,18 +1,44 @@
 #![allow(dead_code)]
 
+pub(super) mod builtins;
+mod env;
 mod errors;
+mod ops;
+mod simplify;
 #[cfg(test)]
 mod tests;
+mod vm;
 
 use std::{fs, path::Path};
 
-use super::{Compile, SOURCE_FILE_EXTENSION};
+use super::{Backend, Compile, CompileOptions, IntDivision, MixingPolicy, SOURCE_FILE_EXTENSION};
 use crate::{
-    ast_lowering::ast::{Ast, BinOp, BinOpKind, Expr, Lit, LiteralKind, Stmt},
+    ast_lowering::{
+        ast::{
+            Ast, BinOp, BinOpKind, Call, Cond, Expr, ExprKind, Ident, Let, Lit, LiteralKind, Stmt,
+            UnOp, UnOpKind,
+        },
+        errors::UnknownIdentifier,
+    },
     errors::diagnostic::{Diagnostic, DiagnosticCtxt},
+    helpers::stack::ensure_sufficient_stack,
     parser::Parser,
+    span::{SourceFile, Span},
 };
-use errors::{MismatchedTypes, OpenFileError, WrongFileExtension};
+use bigdecimal::{BigDecimal, ToPrimitive as _};
+use builtins::{Builtin, BuiltinKind};
+pub use env::Env;
+use errors::{
+    DivisionByZero, IntegerOverflow, MismatchedArgType, MismatchedCondType, MismatchedOperandType,
+    MismatchedTypes, NegativeExponent, NonFiniteBigResult, NonFiniteResult, NonNumericArg,
+    NonNumericOperand, OpenFileError, RedefinedVariable, UnknownFunction, WrongArgCount,
+    WrongFileExtension,
+};
+use num_bigint::BigInt;
+pub use ops::MAX_BIG_BITS;
+use ops::{big_decimal_from_f64, ArithError, IntOperand, Operand};
+use simplify::Simplifier;
+pub use vm::Vm;
 
 type CalcRes<'a> = Result<Lit, Diagnostic<'a>>;
 
@@ -20,6 +46,13 @@ pub struct Program<'a> {
     root: Ast,
     path: String,
     diag_ctxt: &'a DiagnosticCtxt,
+    options: CompileOptions,
+}
+
+impl Drop for Program<'_> {
+    fn drop(&mut self) {
+        self.root.drop_iteratively();
+    }
 }
 
 impl<'a> Compile for Program<'a> {
@@ -34,6 +67,7 @@ impl<'a> Program<'a> {
     pub fn from_source_file(
         path: String,
         diag_ctxt: &'a DiagnosticCtxt,
+        options: CompileOptions,
     ) -> Result<Self, Diagnostic<'a>> {
         let diag_handle = diag_ctxt.handle();
         let file_path = Path::new(&path);
@@ -69,11 +103,7 @@ impl<'a> Program<'a> {
                 }
             };
 
-            Ok(Program::new(
-                Parser::from_source(src.as_ref(), diag_ctxt).lowering_parse()?,
-                path,
-                diag_ctxt,
-            ))
+            Program::from_source_in(src.as_ref(), path, diag_ctxt, &Env::new(), options)
         } else {
             Err(diag_handle.emit_err(WrongFileExtension::new(
                 Some(ext.into()),
@@ -88,18 +118,43 @@ impl<'a> Program<'a> {
         path: String,
         diag_ctxt: &'a DiagnosticCtxt,
     ) -> Result<Self, Diagnostic<'a>> {
+        Program::from_source_in(src, path, diag_ctxt, &Env::new(), CompileOptions::default())
+    }
+
+    /// Same as `Program::from_source`, but the program can use variables from `env`.
+    /// It should be compiled using `Program::compile_in` with the same `env`.
+    pub fn from_source_in(
+        src: &str,
+        path: String,
+        diag_ctxt: &'a DiagnosticCtxt,
+        env: &Env,
+        options: CompileOptions,
+    ) -> Result<Self, Diagnostic<'a>> {
+        // All next diagnostics will point to this source
+        diag_ctxt.set_source_file(SourceFile::new(path.clone(), src.to_string()));
+
+        let root = Parser::from_source(src, diag_ctxt)
+            .lowering_parse(env.names().collect(), options.num_mode)?;
+
         Ok(Program::new(
-            Parser::from_source(src, diag_ctxt).lowering_parse()?,
+            Simplifier::new(options, env).simplify(root),
             path,
             diag_ctxt,
+            options,
         ))
     }
 
-    fn new(root: Ast, path: String, diag_ctxt: &'a DiagnosticCtxt) -> Self {
+    fn new(
+        root: Ast,
+        path: String,
+        diag_ctxt: &'a DiagnosticCtxt,
+        options: CompileOptions,
+    ) -> Self {
         Self {
             root,
             path,
             diag_ctxt,
+            options,
         }
     }
 
@@ -107,92 +162,717 @@ impl<'a> Program<'a> {
         &self.path
     }
 
+    /// Returns the lowered program after constant folding and simplification
+    pub fn get_ast(&self) -> &Ast {
+        &self.root
+    }
+
+    pub fn get_diag_ctxt(&self) -> &'a DiagnosticCtxt {
+        self.diag_ctxt
+    }
+
+    pub fn get_options(&self) -> CompileOptions {
+        self.options
+    }
+
     fn compile(&self) -> CalcRes<'a> {
+        self.compile_in(&mut Env::new())
+    }
+
+    /// Compiles the program using variables from `env` and stores new variables there.
+    /// The result of the program is the result of its last statement.
+    pub fn compile_in(&self, env: &mut Env) -> CalcRes<'a> {
+        match self.options.backend {
+            Backend::Tree => self.walk_in(env),
+            Backend::Vm => Vm::new(self).run_in(env),
+        }
+    }
+
+    fn walk_in(&self, env: &mut Env) -> CalcRes<'a> {
         match &self.root {
-            Ast::Stmt(stmt) => self.compile_stmt(stmt),
+            Ast::Stmts(stmts) => {
+                let mut res = None;
+                for stmt in stmts {
+                    res = Some(self.compile_stmt(stmt, env)?);
+                }
+
+                // Parser doesn't allow empty programs, so we always have at least one statement
+                Ok(res.expect("Program does not contain any statement"))
+            }
         }
     }
 
-    fn compile_stmt(&self, stmt: &Stmt) -> CalcRes<'a> {
+    fn compile_stmt(&self, stmt: &Stmt, env: &mut Env) -> CalcRes<'a> {
         match stmt {
-            Stmt::Expr(expr) => self.compile_expr(expr),
-        }
-    }
-
-    fn compile_expr(&self, expr: &Expr) -> CalcRes<'a> {
-        match expr {
-            Expr::Lit(lit) => Ok(*lit),
-            Expr::BinOp(binop) => self.compile_binop(binop),
-        }
-    }
-
-    fn compile_binop(&self, BinOp { lhs, rhs, op }: &BinOp) -> CalcRes<'a> {
-        match (self.compile_expr(lhs)?, self.compile_expr(rhs)?) {
-            (
-                Lit {
-                    kind: LiteralKind::Int { val: lhs },
-                },
-                Lit {
-                    kind: LiteralKind::Int { val: rhs },
-                },
-            ) => Ok(Lit {
-                kind: LiteralKind::Int {
-                    val: self.apply_binop(lhs, rhs, *op),
-                },
-            }),
-            (
-                Lit {
-                    kind: LiteralKind::Float { val: lhs },
-                },
-                Lit {
-                    kind: LiteralKind::Float { val: rhs },
-                },
-            ) => Ok(Lit {
-                kind: LiteralKind::Float {
-                    val: self.apply_binop(lhs, rhs, *op),
-                },
-            }),
-            (Lit { kind: lty }, Lit { kind: rty }) => {
-                self.diag_ctxt.handle().emit_warn(MismatchedTypes::new(
-                    format!("{:?}", lty),
-                    format!("{:?}", rty),
+            Stmt::Expr(expr) => self.compile_expr(expr, env),
+            Stmt::Let(let_stmt) => self.compile_let(let_stmt, env),
+        }
+    }
+
+    fn compile_let(&self, Let { ident, expr }: &Let, env: &mut Env) -> CalcRes<'a> {
+        self.check_undefined(ident, env)?;
+
+        let val = self.compile_expr(expr, env)?;
+        env.define(ident.name.clone(), val.clone());
+
+        Ok(val)
+    }
+
+    // Variables can't be redefined, it's checked before their value is calculated
+    fn check_undefined(&self, ident: &Ident, env: &Env) -> Result<(), Diagnostic<'a>> {
+        if env.contains(&ident.name) {
+            return Err(self
+                .diag_ctxt
+                .handle()
+                .emit_err(RedefinedVariable::new(ident.name.clone(), ident.span)));
+        }
+
+        Ok(())
+    }
+
+    fn compile_expr(&self, Expr { kind, span }: &Expr, env: &Env) -> CalcRes<'a> {
+        ensure_sufficient_stack(|| match kind {
+            ExprKind::Lit(lit) => Ok(lit.clone()),
+            ExprKind::BinOp(binop) => self.compile_binop(binop, *span, env),
+            ExprKind::UnOp(unop) => self.compile_unop(unop, *span, env),
+            ExprKind::Ident(ident) => self.compile_ident(ident, env),
+            ExprKind::Call(call) => self.compile_call(call, *span, env),
+            ExprKind::Cond(cond) => self.compile_cond(cond, env),
+        })
+    }
+
+    // Only the branch that is chosen by the condition is calculated
+    fn compile_cond(
+        &self,
+        Cond {
+            cond,
+            if_true,
+            if_false,
+        }: &Cond,
+        env: &Env,
+    ) -> CalcRes<'a> {
+        let val = self.compile_expr(cond, env)?.kind;
+
+        if self.cond_value(val, cond.span)? {
+            self.compile_expr(if_true, env)
+        } else {
+            self.compile_expr(if_false, env)
+        }
+    }
+
+    fn cond_value(&self, val: LiteralKind, span: Span) -> Result<bool, Diagnostic<'a>> {
+        match val {
+            LiteralKind::Bool { val } => Ok(val),
+            kind => Err(self
+                .diag_ctxt
+                .handle()
+                .emit_err(MismatchedCondType::new(format!("{kind:?}"), span))),
+        }
+    }
+
+    // Unknown names are reported by the lowering, so this only happens if the program
+    // is compiled in another `Env` than it was lowered with. It's the same diagnostic anyway
+    fn compile_ident(&self, Ident { name, span }: &Ident, env: &Env) -> CalcRes<'a> {
+        match env.get(name) {
+            Some(val) => Ok(val),
+            None => Err(self
+                .diag_ctxt
+                .handle()
+                .emit_err(UnknownIdentifier::new(name.clone(), *span))),
+        }
+    }
+
+    fn compile_call(&self, Call { ident, args }: &Call, span: Span, env: &Env) -> CalcRes<'a> {
+        let builtin = self.lookup_call(ident, args.len(), span)?;
+        let vals = args
+            .iter()
+            .map(|arg| self.compile_expr(arg, env).map(|lit| lit.kind))
+            .collect::<Result<Vec<_>, _>>()?;
+        let spans: Vec<_> = args.iter().map(|arg| arg.span).collect();
+
+        self.eval_call(builtin, ident, vals, &spans, span)
+    }
+
+    // The function is checked before its arguments are calculated
+    fn lookup_call(
+        &self,
+        ident: &Ident,
+        args: usize,
+        span: Span,
+    ) -> Result<&'static Builtin, Diagnostic<'a>> {
+        let handle = self.diag_ctxt.handle();
+        let builtin = match builtins::lookup(&ident.name) {
+            Some(builtin) => builtin,
+            None => {
+                return Err(handle.emit_err(UnknownFunction::new(ident.name.clone(), ident.span)))
+            }
+        };
+        if !builtin.arity.accepts(args) {
+            return Err(handle.emit_err(WrongArgCount::new(
+                ident.name.clone(),
+                builtin.arity.to_string(),
+                args,
+                span,
+            )));
+        }
+
+        Ok(builtin)
+    }
+
+    // `spans` are the spans of the arguments
+    fn eval_call(
+        &self,
+        builtin: &Builtin,
+        ident: &Ident,
+        vals: Vec<LiteralKind>,
+        spans: &[Span],
+        span: Span,
+    ) -> CalcRes<'a> {
+        let handle = self.diag_ctxt.handle();
+        if let Some(bool) = vals.iter().position(is_bool) {
+            return Err(handle.emit_err(NonNumericArg::new(
+                ident.name.clone(),
+                format!("{:?}", vals[bool]),
+                spans[bool],
+            )));
+        }
+        let expr = format!(
+            "{}({})",
+            ident.name,
+            vals.iter().map(repr).collect::<Vec<_>>().join(", ")
+        );
+        let arith_err = |err| self.arith_err(err, expr.clone(), span);
+
+        let kind = match builtin.kind {
+            BuiltinKind::Float(f) => return self.apply_float_fn(f, &vals, expr, span),
+            BuiltinKind::Num(f) => {
+                // If integers and floats are mixed, all of them are converted to floats
+                self.check_mismatched(&vals, span)?;
+
+                match unify(vals) {
+                    Operands::Int(args) => LiteralKind::Int {
+                        val: f.apply(&args).map_err(arith_err)?,
+                    },
+                    Operands::Float(args) => LiteralKind::Float {
+                        val: f.apply(&args).map_err(arith_err)?,
+                    },
+                    Operands::BigInt(args) => LiteralKind::BigInt {
+                        val: f.apply(&args).map_err(arith_err)?,
+                    },
+                    Operands::BigDecimal(args) => LiteralKind::BigDecimal {
+                        val: f.apply(&args).map_err(arith_err)?,
+                    },
+                }
+            }
+            BuiltinKind::Int(f) => {
+                if let Some(float) = vals.iter().position(is_float) {
+                    return Err(handle.emit_err(MismatchedArgType::new(
+                        ident.name.clone(),
+                        "Int".to_string(),
+                        format!("{:?}", vals[float]),
+                        spans[float],
+                    )));
+                }
+
+                match unify(vals) {
+                    Operands::Int(args) => LiteralKind::Int {
+                        val: f.apply(&args).map_err(arith_err)?,
+                    },
+                    Operands::BigInt(args) => LiteralKind::BigInt {
+                        val: f.apply(&args).map_err(arith_err)?,
+                    },
+                    Operands::Float(_) | Operands::BigDecimal(_) => {
+                        unreachable!("Arguments of integer functions are integers")
+                    }
+                }
+            }
+        };
+
+        Ok(Lit { kind })
+    }
+
+    // Big numbers are also calculated with floats, so their result is converted back
+    fn apply_float_fn(
+        &self,
+        f: fn(&[f64]) -> f64,
+        vals: &[LiteralKind],
+        expr: String,
+        span: Span,
+    ) -> CalcRes<'a> {
+        let args: Vec<_> = vals.iter().map(to_f64).collect();
+        let res = f(&args);
+
+        let kind = if vals.iter().any(is_big) {
+            match big_decimal_from_f64(res) {
+                Some(val) => LiteralKind::BigDecimal { val },
+                None => return Err(self.arith_err(ArithError::NonFinite, expr, span)),
+            }
+        } else {
+            // Same as for binary operations, dont warn if we've got `inf` or `NaN` from the arguments
+            if !res.is_finite() && args.iter().all(|arg| arg.is_finite()) {
+                self.diag_ctxt.handle().emit_warn(NonFiniteResult::new(
+                    expr,
+                    format!("{res:?}"),
+                    span,
                 ));
+            }
+            LiteralKind::Float { val: res }
+        };
 
-                let (lhs, rhs) = self.mismatched_lit_ty(lty, rty);
-                let res = self.apply_binop(lhs, rhs, *op);
-                Ok(Lit {
-                    kind: LiteralKind::Float { val: res },
-                })
+        Ok(Lit { kind })
+    }
+
+    fn compile_unop(&self, UnOp { expr, op }: &UnOp, span: Span, env: &Env) -> CalcRes<'a> {
+        let Lit { kind } = self.compile_expr(expr, env)?;
+
+        self.eval_unop(*op, kind, expr.span, span)
+    }
+
+    // `operand_span` is the span of the operand and `span` is the span of the whole operation
+    fn eval_unop(
+        &self,
+        op: UnOpKind,
+        kind: LiteralKind,
+        operand_span: Span,
+        span: Span,
+    ) -> CalcRes<'a> {
+        let kind = match (op, kind) {
+            (UnOpKind::Not, LiteralKind::Bool { val }) => LiteralKind::Bool { val: !val },
+            (UnOpKind::Not, kind) => {
+                let err = self
+                    .diag_ctxt
+                    .handle()
+                    .struct_err(MismatchedOperandType::new(
+                        "!".to_string(),
+                        "Bool".to_string(),
+                        format!("{kind:?}"),
+                        operand_span,
+                    ));
+                let err = match kind {
+                    LiteralKind::Int { .. } | LiteralKind::BigInt { .. } => {
+                        err.with_help("use `~` for bitwise not of an integer")
+                    }
+                    _ => err,
+                };
+                err.emit();
+
+                return Err(err);
+            }
+            (UnOpKind::Pos | UnOpKind::Neg, LiteralKind::Bool { val }) => {
+                return Err(self.diag_ctxt.handle().emit_err(NonNumericOperand::new(
+                    if op == UnOpKind::Pos { "+" } else { "-" }.to_string(),
+                    format!("{:?}", LiteralKind::Bool { val }),
+                    operand_span,
+                )))
+            }
+            (UnOpKind::Pos, kind) => kind,
+            (UnOpKind::Neg, LiteralKind::Int { val }) => LiteralKind::Int {
+                val: val.checked_neg().ok_or_else(|| {
+                    self.arith_err(ArithError::Overflow, format!("-({val})"), span)
+                })?,
+            },
+            (UnOpKind::Neg, LiteralKind::Float { val }) => LiteralKind::Float { val: -val },
+            (UnOpKind::Neg, LiteralKind::BigInt { val }) => LiteralKind::BigInt { val: -val },
+            (UnOpKind::Neg, LiteralKind::BigDecimal { val }) => {
+                LiteralKind::BigDecimal { val: -val }
             }
+            (UnOpKind::BitNot, LiteralKind::Int { val }) => LiteralKind::Int { val: !val },
+            (UnOpKind::BitNot, LiteralKind::BigInt { val }) => LiteralKind::BigInt { val: !val },
+            (UnOpKind::BitNot, kind) => {
+                return Err(self.diag_ctxt.handle().emit_err(MismatchedOperandType::new(
+                    "~".to_string(),
+                    "Int".to_string(),
+                    format!("{kind:?}"),
+                    operand_span,
+                )))
+            }
+        };
+
+        Ok(Lit { kind })
+    }
+
+    fn compile_binop(&self, BinOp { lhs, rhs, op }: &BinOp, span: Span, env: &Env) -> CalcRes<'a> {
+        if matches!(op, BinOpKind::And | BinOpKind::Or) {
+            return self.compile_logical(lhs, rhs, *op, env);
         }
+
+        let vals = vec![
+            self.compile_expr(lhs, env)?.kind,
+            self.compile_expr(rhs, env)?.kind,
+        ];
+
+        self.eval_binop(*op, vals, [lhs.span, rhs.span], span)
     }
 
-    // if we see mismatched types, try to convert it to one type
-    fn mismatched_lit_ty(&self, lty: LiteralKind, rty: LiteralKind) -> (f32, f32) {
-        (self.to_float_ty(lty), self.to_float_ty(rty))
+    // Applies any binary operator except `&&` and `||`, which don't always calculate
+    // their right operand. `spans` are the spans of the operands.
+    fn eval_binop(
+        &self,
+        op: BinOpKind,
+        vals: Vec<LiteralKind>,
+        spans: [Span; 2],
+        span: Span,
+    ) -> CalcRes<'a> {
+        if op.is_comparison() {
+            return self.compile_comparison(vals, spans, op, span);
+        }
+        if op.is_bitwise() {
+            return self.compile_bitwise(vals, spans, op, span);
+        }
+        if let Some(bool) = vals.iter().position(is_bool) {
+            return Err(self.diag_ctxt.handle().emit_err(NonNumericOperand::new(
+                op.to_string(),
+                format!("{:?}", vals[bool]),
+                spans[bool],
+            )));
+        }
+        // if we see mismatched types, convert them to one type
+        self.check_mismatched(&vals, span)?;
+
+        let kind = match unify(vals) {
+            Operands::Int(vals)
+                if op == BinOpKind::Div && self.options.int_div == IntDivision::Float =>
+            {
+                let vals: Vec<_> = vals.into_iter().map(|val| val as f64).collect();
+                LiteralKind::Float {
+                    val: self.apply_binop(&vals[0], &vals[1], op, span)?,
+                }
+            }
+            Operands::BigInt(vals)
+                if op == BinOpKind::Div && self.options.int_div == IntDivision::Float =>
+            {
+                let vals: Vec<_> = vals.into_iter().map(BigDecimal::from).collect();
+                LiteralKind::BigDecimal {
+                    val: self.apply_binop(&vals[0], &vals[1], op, span)?,
+                }
+            }
+            Operands::Int(vals) => LiteralKind::Int {
+                val: self.apply_binop(&vals[0], &vals[1], op, span)?,
+            },
+            Operands::Float(vals) => LiteralKind::Float {
+                val: self.apply_binop(&vals[0], &vals[1], op, span)?,
+            },
+            Operands::BigInt(vals) => LiteralKind::BigInt {
+                val: self.apply_binop(&vals[0], &vals[1], op, span)?,
+            },
+            Operands::BigDecimal(vals) => LiteralKind::BigDecimal {
+                val: self.apply_binop(&vals[0], &vals[1], op, span)?,
+            },
+        };
+
+        Ok(Lit { kind })
     }
 
-    fn to_float_ty(&self, ty: LiteralKind) -> f32 {
-        match ty {
-            LiteralKind::Int { val } => val as f32,
-            LiteralKind::Float { val } => val,
+    // Reports integers mixed with floats as the mixing policy says
+    fn check_mismatched(&self, vals: &[LiteralKind], span: Span) -> Result<(), Diagnostic<'a>> {
+        let (Some(int), Some(float)) = (
+            vals.iter().find(|val| !is_float(val)),
+            vals.iter().find(|val| is_float(val)),
+        ) else {
+            return Ok(());
+        };
+        let handle = self.diag_ctxt.handle();
+        let mismatched = MismatchedTypes::new(format!("{:?}", int), format!("{:?}", float), span);
+
+        match self.options.mixing {
+            MixingPolicy::Promote => handle.emit_warn(mismatched),
+            MixingPolicy::Strict => {
+                let err = handle
+                    .struct_err(mismatched)
+                    .with_help("use `--mixing=promote` to convert integers to floats");
+                err.emit();
+
+                return Err(err);
+            }
+            MixingPolicy::Silent => (),
         }
+
+        Ok(())
     }
 
-    fn apply_binop<T>(&self, lhs: T, rhs: T, op: BinOpKind) -> T
-    where
-        T: std::ops::Add<Output = T>
-            + std::ops::Sub<Output = T>
-            + std::ops::Mul<Output = T>
-            + std::ops::Div<Output = T>
-            + std::ops::Rem<Output = T>,
-    {
-        match op {
-            BinOpKind::Add => lhs + rhs,
-            BinOpKind::Sub => lhs - rhs,
-            BinOpKind::Mul => lhs * rhs,
-            BinOpKind::Div => lhs / rhs,
-            BinOpKind::Mod => lhs % rhs,
+    fn apply_binop<T: Operand>(
+        &self,
+        lhs: &T,
+        rhs: &T,
+        op: BinOpKind,
+        span: Span,
+    ) -> Result<T, Diagnostic<'a>> {
+        let res = match op {
+            BinOpKind::Add => lhs.add(rhs),
+            BinOpKind::Sub => lhs.sub(rhs),
+            BinOpKind::Mul => lhs.mul(rhs),
+            BinOpKind::Div => lhs.div(rhs),
+            BinOpKind::Mod => lhs.rem(rhs),
+            BinOpKind::Pow => lhs.pow(rhs),
+            _ => unreachable!("`{op}` is not an arithmetic operator"),
+        };
+        let expr = format!("{} {op} {}", lhs.repr(), rhs.repr());
+
+        match res {
+            Ok(res) => {
+                // Don't warn again if we've got `inf` or `NaN` from the operands
+                if !res.is_finite() && lhs.is_finite() && rhs.is_finite() {
+                    self.diag_ctxt
+                        .handle()
+                        .emit_warn(NonFiniteResult::new(expr, res.repr(), span));
+                }
+                Ok(res)
+            }
+            Err(err) => Err(self.arith_err(err, expr, span)),
+        }
+    }
+
+    // `&&` and `||` calculate the right operand only if the left one doesn't decide the result
+    fn compile_logical(&self, lhs: &Expr, rhs: &Expr, op: BinOpKind, env: &Env) -> CalcRes<'a> {
+        let val = match (op, self.compile_bool_operand(lhs, op, env)?) {
+            (BinOpKind::And, false) => false,
+            (BinOpKind::Or, true) => true,
+            _ => self.compile_bool_operand(rhs, op, env)?,
+        };
+
+        Ok(Lit {
+            kind: LiteralKind::Bool { val },
+        })
+    }
+
+    fn compile_bool_operand(
+        &self,
+        expr: &Expr,
+        op: BinOpKind,
+        env: &Env,
+    ) -> Result<bool, Diagnostic<'a>> {
+        let val = self.compile_expr(expr, env)?.kind;
+
+        self.bool_operand(val, op, expr.span)
+    }
+
+    fn bool_operand(
+        &self,
+        val: LiteralKind,
+        op: BinOpKind,
+        span: Span,
+    ) -> Result<bool, Diagnostic<'a>> {
+        match val {
+            LiteralKind::Bool { val } => Ok(val),
+            kind => Err(self.diag_ctxt.handle().emit_err(MismatchedOperandType::new(
+                op.to_string(),
+                "Bool".to_string(),
+                format!("{kind:?}"),
+                span,
+            ))),
         }
     }
+
+    // Numbers can be compared with numbers and booleans only with booleans
+    fn compile_comparison(
+        &self,
+        vals: Vec<LiteralKind>,
+        spans: [Span; 2],
+        op: BinOpKind,
+        span: Span,
+    ) -> CalcRes<'a> {
+        let handle = self.diag_ctxt.handle();
+
+        let val = match (&vals[0], &vals[1]) {
+            (LiteralKind::Bool { val: lhs }, LiteralKind::Bool { val: rhs }) => match op {
+                BinOpKind::Eq => lhs == rhs,
+                BinOpKind::Ne => lhs != rhs,
+                // Booleans are not ordered
+                _ => {
+                    return Err(handle.emit_err(NonNumericOperand::new(
+                        op.to_string(),
+                        format!("{:?}", vals[0]),
+                        spans[0],
+                    )))
+                }
+            },
+            (lhs @ LiteralKind::Bool { .. }, rhs) | (lhs, rhs @ LiteralKind::Bool { .. }) => {
+                return Err(handle.emit_err(MismatchedTypes::new(
+                    format!("{lhs:?}"),
+                    format!("{rhs:?}"),
+                    span,
+                )))
+            }
+            _ => {
+                self.check_mismatched(&vals, span)?;
+
+                match unify(vals) {
+                    Operands::Int(vals) => compare(&vals[0], &vals[1], op),
+                    Operands::Float(vals) => compare(&vals[0], &vals[1], op),
+                    Operands::BigInt(vals) => compare(&vals[0], &vals[1], op),
+                    Operands::BigDecimal(vals) => compare(&vals[0], &vals[1], op),
+                }
+            }
+        };
+
+        Ok(Lit {
+            kind: LiteralKind::Bool { val },
+        })
+    }
+
+    // Bitwise operators can be applied only to integers, so floats are never converted here
+    fn compile_bitwise(
+        &self,
+        vals: Vec<LiteralKind>,
+        spans: [Span; 2],
+        op: BinOpKind,
+        span: Span,
+    ) -> CalcRes<'a> {
+        if let Some(float) = vals.iter().position(|val| !is_int(val)) {
+            return Err(self.diag_ctxt.handle().emit_err(MismatchedOperandType::new(
+                op.to_string(),
+                "Int".to_string(),
+                format!("{:?}", vals[float]),
+                spans[float],
+            )));
+        }
+
+        let kind = match unify(vals) {
+            Operands::Int(vals) => LiteralKind::Int {
+                val: self.apply_bitwise(&vals[0], &vals[1], op, span)?,
+            },
+            Operands::BigInt(vals) => LiteralKind::BigInt {
+                val: self.apply_bitwise(&vals[0], &vals[1], op, span)?,
+            },
+            Operands::Float(_) | Operands::BigDecimal(_) => {
+                unreachable!("Operands of bitwise operators are integers")
+            }
+        };
+
+        Ok(Lit { kind })
+    }
+
+    fn apply_bitwise<T: IntOperand>(
+        &self,
+        lhs: &T,
+        rhs: &T,
+        op: BinOpKind,
+        span: Span,
+    ) -> Result<T, Diagnostic<'a>> {
+        let res = match op {
+            BinOpKind::BitAnd => lhs.bit_and(rhs),
+            BinOpKind::BitOr => lhs.bit_or(rhs),
+            BinOpKind::BitXor => lhs.bit_xor(rhs),
+            BinOpKind::Shl => lhs.shl(rhs),
+            BinOpKind::Shr => lhs.shr(rhs),
+            _ => unreachable!("`{op}` is not a bitwise operator"),
+        };
+
+        res.map_err(|err| self.arith_err(err, format!("{} {op} {}", lhs.repr(), rhs.repr()), span))
+    }
+
+    fn arith_err(&self, err: ArithError, expr: String, span: Span) -> Diagnostic<'a> {
+        let handle = self.diag_ctxt.handle();
+
+        match err {
+            ArithError::Overflow => handle.emit_err(IntegerOverflow::new(expr, span)),
+            ArithError::DivisionByZero => handle.emit_err(DivisionByZero::new(expr, span)),
+            ArithError::NegativeExponent => handle.emit_err(NegativeExponent::new(expr, span)),
+            ArithError::NonFinite => handle.emit_err(NonFiniteBigResult::new(expr, span)),
+        }
+    }
+}
+
+/// Values of the same type, so we can apply an operation to them.
+enum Operands {
+    Int(Vec<i64>),
+    Float(Vec<f64>),
+    BigInt(Vec<BigInt>),
+    BigDecimal(Vec<BigDecimal>),
+}
+
+// Converts all values to the widest type of them: integers are converted to floats
+// if there is at least one float. Native and big numbers are never mixed,
+// because all numbers of the program are lowered in the same mode.
+fn unify(vals: Vec<LiteralKind>) -> Operands {
+    let has_float = vals.iter().any(is_float);
+
+    if vals.iter().any(is_big) {
+        if has_float {
+            Operands::BigDecimal(
+                vals.into_iter()
+                    .map(|val| match val {
+                        LiteralKind::BigInt { val } => BigDecimal::from(val),
+                        LiteralKind::BigDecimal { val } => val,
+                        _ => unreachable!("Native and big numbers are never mixed"),
+                    })
+                    .collect(),
+            )
+        } else {
+            Operands::BigInt(
+                vals.into_iter()
+                    .map(|val| match val {
+                        LiteralKind::BigInt { val } => val,
+                        _ => unreachable!("Native and big numbers are never mixed"),
+                    })
+                    .collect(),
+            )
+        }
+    } else if has_float {
+        Operands::Float(vals.iter().map(to_f64).collect())
+    } else {
+        Operands::Int(
+            vals.into_iter()
+                .map(|val| match val {
+                    LiteralKind::Int { val } => val,
+                    _ => unreachable!("Native and big numbers are never mixed"),
+                })
+                .collect(),
+        )
+    }
+}
+
+fn compare<T: PartialOrd>(lhs: &T, rhs: &T, op: BinOpKind) -> bool {
+    match op {
+        BinOpKind::Lt => lhs < rhs,
+        BinOpKind::Le => lhs <= rhs,
+        BinOpKind::Gt => lhs > rhs,
+        BinOpKind::Ge => lhs >= rhs,
+        BinOpKind::Eq => lhs == rhs,
+        BinOpKind::Ne => lhs != rhs,
+        _ => unreachable!("`{op}` is not a comparison operator"),
+    }
+}
+
+fn is_int(val: &LiteralKind) -> bool {
+    matches!(val, LiteralKind::Int { .. } | LiteralKind::BigInt { .. })
+}
+
+fn is_bool(val: &LiteralKind) -> bool {
+    matches!(val, LiteralKind::Bool { .. })
+}
+
+fn is_float(val: &LiteralKind) -> bool {
+    matches!(
+        val,
+        LiteralKind::Float { .. } | LiteralKind::BigDecimal { .. }
+    )
+}
+
+fn is_big(val: &LiteralKind) -> bool {
+    matches!(
+        val,
+        LiteralKind::BigInt { .. } | LiteralKind::BigDecimal { .. }
+    )
+}
+
+fn to_f64(val: &LiteralKind) -> f64 {
+    match val {
+        LiteralKind::Int { val } => *val as f64,
+        LiteralKind::Float { val } => *val,
+        LiteralKind::BigInt { val } => val.to_f64().unwrap_or(f64::NAN),
+        LiteralKind::BigDecimal { val } => val.to_f64().unwrap_or(f64::NAN),
+        LiteralKind::Bool { .. } => unreachable!("Booleans are never converted to numbers"),
+    }
+}
+
+// How the value is shown in diagnostics
+fn repr(val: &LiteralKind) -> String {
+    match val {
+        LiteralKind::Int { val } => val.repr(),
+        LiteralKind::Float { val } => val.repr(),
+        LiteralKind::BigInt { val } => val.repr(),
+        LiteralKind::BigDecimal { val } => val.repr(),
+        LiteralKind::Bool { val } => val.to_string(),
+    }
 }
@@ -0,0 +1,142 @@
+use super::ops::{ArithError, IntOperand, Operand};
+
+/// Function that is available in every program without a definition.
+#[derive(Clone, Copy, Debug)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub arity: Arity,
+    pub kind: BuiltinKind,
+}
+
+/// Number of arguments that a builtin function takes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arity {
+    Exact(usize),
+    AtLeast(usize),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum BuiltinKind {
+    // Takes floats (integer arguments are converted to floats) and returns a float.
+    // Big numbers are also converted to floats, so the result is not exact.
+    Float(fn(&[f64]) -> f64),
+    // Works with any numbers and returns the type of its arguments
+    Num(NumFn),
+    // Takes only integers
+    Int(IntFn),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumFn {
+    Abs,
+    Min,
+    Max,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntFn {
+    Gcd,
+}
+
+static BUILTINS: &[Builtin] = &[
+    float("sqrt", 1, |args| args[0].sqrt()),
+    float("cbrt", 1, |args| args[0].cbrt()),
+    float("exp", 1, |args| args[0].exp()),
+    float("ln", 1, |args| args[0].ln()),
+    float("log2", 1, |args| args[0].log2()),
+    float("log10", 1, |args| args[0].log10()),
+    float("sin", 1, |args| args[0].sin()),
+    float("cos", 1, |args| args[0].cos()),
+    float("tan", 1, |args| args[0].tan()),
+    float("asin", 1, |args| args[0].asin()),
+    float("acos", 1, |args| args[0].acos()),
+    float("atan", 1, |args| args[0].atan()),
+    float("atan2", 2, |args| args[0].atan2(args[1])),
+    float("hypot", 2, |args| args[0].hypot(args[1])),
+    float("floor", 1, |args| args[0].floor()),
+    float("ceil", 1, |args| args[0].ceil()),
+    float("round", 1, |args| args[0].round()),
+    Builtin {
+        name: "abs",
+        arity: Arity::Exact(1),
+        kind: BuiltinKind::Num(NumFn::Abs),
+    },
+    Builtin {
+        name: "min",
+        arity: Arity::AtLeast(1),
+        kind: BuiltinKind::Num(NumFn::Min),
+    },
+    Builtin {
+        name: "max",
+        arity: Arity::AtLeast(1),
+        kind: BuiltinKind::Num(NumFn::Max),
+    },
+    Builtin {
+        name: "gcd",
+        arity: Arity::Exact(2),
+        kind: BuiltinKind::Int(IntFn::Gcd),
+    },
+];
+
+/// Returns the builtin function with the name `name`, if there is one
+pub fn lookup(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+const fn float(name: &'static str, arity: usize, f: fn(&[f64]) -> f64) -> Builtin {
+    Builtin {
+        name,
+        arity: Arity::Exact(arity),
+        kind: BuiltinKind::Float(f),
+    }
+}
+
+impl NumFn {
+    // Arity is already checked, so there is at least one argument
+    pub fn apply<T: Operand>(self, args: &[T]) -> Result<T, ArithError> {
+        let first = args[0].clone();
+
+        match self {
+            NumFn::Abs => first.abs(),
+            NumFn::Min => {
+                Ok(args[1..]
+                    .iter()
+                    .fold(first, |min, arg| if *arg < min { arg.clone() } else { min }))
+            }
+            NumFn::Max => {
+                Ok(args[1..]
+                    .iter()
+                    .fold(first, |max, arg| if *arg > max { arg.clone() } else { max }))
+            }
+        }
+    }
+}
+
+impl IntFn {
+    pub fn apply<T: IntOperand>(self, args: &[T]) -> Result<T, ArithError> {
+        match self {
+            IntFn::Gcd => args[0].gcd(&args[1]),
+        }
+    }
+}
+
+impl Arity {
+    pub fn accepts(self, args: usize) -> bool {
+        match self {
+            Arity::Exact(n) => args == n,
+            Arity::AtLeast(n) => args >= n,
+        }
+    }
+}
+
+impl std::fmt::Display for Arity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (prefix, n) = match self {
+            Arity::Exact(n) => ("", n),
+            Arity::AtLeast(n) => ("at least ", n),
+        };
+        let s = if *n == 1 { "" } else { "s" };
+
+        write!(f, "{prefix}{n} argument{s}")
+    }
+}
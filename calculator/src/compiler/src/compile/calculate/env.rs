@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+
+use crate::ast_lowering::ast::Lit;
+
+/// Stores the values of variables defined by `let` statements.
+/// It can outlive the `Program`, so several programs can share their variables (for example, in REPL).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Env {
+    vars: HashMap<String, Lit>,
+}
+
+impl Env {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Lit> {
+        self.vars.get(name).cloned()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.vars.contains_key(name)
+    }
+
+    pub fn define(&mut self, name: String, val: Lit) {
+        self.vars.insert(name, val);
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = String> + '_ {
+        self.vars.keys().cloned()
+    }
+}
@@ -1,4 +1,5 @@
 use crate::errors::diagnostic::{Diagnostic, DiagnosticCtxt, DiagnosticMsg, IntoDiagnostic};
+use crate::span::Span;
 use calculator_macros::IntoDiagnostic;
 
 #[derive(IntoDiagnostic)]
@@ -32,15 +33,223 @@ impl WrongFileExtension {
     }
 }
 
+#[derive(IntoDiagnostic)]
+#[diagnostic("variable `{}` is already defined")]
+pub struct RedefinedVariable {
+    name: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl RedefinedVariable {
+    pub fn new(name: String, span: Span) -> Self {
+        Self { name, span }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("attempt to compute `{}`, which would overflow")]
+pub struct IntegerOverflow {
+    expr: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl IntegerOverflow {
+    pub fn new(expr: String, span: Span) -> Self {
+        Self { expr, span }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("attempt to compute `{}`, which is a division by zero")]
+pub struct DivisionByZero {
+    expr: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl DivisionByZero {
+    pub fn new(expr: String, span: Span) -> Self {
+        Self { expr, span }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("result of `{}` is `{}`")]
+pub struct NonFiniteResult {
+    expr: String,
+    res: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl NonFiniteResult {
+    pub fn new(expr: String, res: String, span: Span) -> Self {
+        Self { expr, res, span }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("attempt to compute `{}` with a negative integer exponent, use a float instead")]
+pub struct NegativeExponent {
+    expr: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl NegativeExponent {
+    pub fn new(expr: String, span: Span) -> Self {
+        Self { expr, span }
+    }
+}
+
 #[derive(IntoDiagnostic)]
 #[diagnostic("mismatched types: `{}` and `{}`")]
 pub struct MismatchedTypes {
-    rty: String,
     lty: String,
+    rty: String,
+    #[primary_span]
+    span: Span,
 }
 
 impl MismatchedTypes {
-    pub fn new(lty: String, rty: String) -> Self {
-        Self { lty, rty }
+    pub fn new(lty: String, rty: String, span: Span) -> Self {
+        Self { lty, rty, span }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("operator `{}` expected an operand of type `{}`, found `{}`")]
+pub struct MismatchedOperandType {
+    op: String,
+    expected: String,
+    found: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl MismatchedOperandType {
+    pub fn new(op: String, expected: String, found: String, span: Span) -> Self {
+        Self {
+            op,
+            expected,
+            found,
+            span,
+        }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("operator `{}` expected a number, found `{}`")]
+pub struct NonNumericOperand {
+    op: String,
+    found: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl NonNumericOperand {
+    pub fn new(op: String, found: String, span: Span) -> Self {
+        Self { op, found, span }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("function `{}` expected a number, found `{}`")]
+pub struct NonNumericArg {
+    name: String,
+    found: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl NonNumericArg {
+    pub fn new(name: String, found: String, span: Span) -> Self {
+        Self { name, found, span }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("expected a condition of type `Bool`, found `{}`")]
+pub struct MismatchedCondType {
+    found: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl MismatchedCondType {
+    pub fn new(found: String, span: Span) -> Self {
+        Self { found, span }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("cannot find function `{}` in this scope")]
+pub struct UnknownFunction {
+    name: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl UnknownFunction {
+    pub fn new(name: String, span: Span) -> Self {
+        Self { name, span }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("function `{}` takes {}, but got {}")]
+pub struct WrongArgCount {
+    name: String,
+    expected: String,
+    found: usize,
+    #[primary_span]
+    span: Span,
+}
+
+impl WrongArgCount {
+    pub fn new(name: String, expected: String, found: usize, span: Span) -> Self {
+        Self {
+            name,
+            expected,
+            found,
+            span,
+        }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("function `{}` expected an argument of type `{}`, found `{}`")]
+pub struct MismatchedArgType {
+    name: String,
+    expected: String,
+    found: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl MismatchedArgType {
+    pub fn new(name: String, expected: String, found: String, span: Span) -> Self {
+        Self {
+            name,
+            expected,
+            found,
+            span,
+        }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("result of `{}` is not a finite number, so it can't be a big decimal")]
+pub struct NonFiniteBigResult {
+    expr: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl NonFiniteBigResult {
+    pub fn new(expr: String, span: Span) -> Self {
+        Self { expr, span }
     }
 }
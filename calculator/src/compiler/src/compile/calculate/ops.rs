@@ -0,0 +1,375 @@
+use std::{fmt::Debug, str::FromStr as _};
+
+use bigdecimal::{BigDecimal, One as _, ToPrimitive as _, Zero as _};
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{Pow, Signed};
+
+/// Results of big numbers are limited to this number of bits (about 315 thousand decimal digits),
+/// so `2 ** 4000000000` overflows at once instead of calculating until it's out of memory.
+pub const MAX_BIG_BITS: u64 = 1 << 20;
+
+/// Errors of arithmetic operations that can't be represented as a value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArithError {
+    Overflow,
+    DivisionByZero,
+    NegativeExponent,
+    // Big decimals can't be `inf` or `NaN`
+    NonFinite,
+}
+
+/// Types that can be operands of binary operations in `Program::apply_binop`.
+/// Integer operations are checked, float operations follow IEEE 754 and never fail.
+/// Big numbers overflow only if the result has more than `MAX_BIG_BITS`,
+/// and they can't be divided by zero either.
+pub trait Operand: Clone + Debug + PartialOrd {
+    fn add(&self, rhs: &Self) -> Result<Self, ArithError>;
+    fn sub(&self, rhs: &Self) -> Result<Self, ArithError>;
+    fn mul(&self, rhs: &Self) -> Result<Self, ArithError>;
+    fn div(&self, rhs: &Self) -> Result<Self, ArithError>;
+    fn rem(&self, rhs: &Self) -> Result<Self, ArithError>;
+    fn pow(&self, exp: &Self) -> Result<Self, ArithError>;
+    fn abs(&self) -> Result<Self, ArithError>;
+    // `inf` and `NaN` are valid values, but they are most likely not what the user wanted
+    fn is_finite(&self) -> bool;
+    // How the value is shown in diagnostics
+    fn repr(&self) -> String;
+}
+
+/// Operands that are always integers.
+/// Shifts by a negative number or by the number of bits of the type or more overflow.
+pub trait IntOperand: Operand {
+    fn gcd(&self, rhs: &Self) -> Result<Self, ArithError>;
+    fn bit_and(&self, rhs: &Self) -> Result<Self, ArithError>;
+    fn bit_or(&self, rhs: &Self) -> Result<Self, ArithError>;
+    fn bit_xor(&self, rhs: &Self) -> Result<Self, ArithError>;
+    fn shl(&self, rhs: &Self) -> Result<Self, ArithError>;
+    fn shr(&self, rhs: &Self) -> Result<Self, ArithError>;
+    fn not(&self) -> Result<Self, ArithError>;
+}
+
+impl Operand for i64 {
+    fn add(&self, rhs: &Self) -> Result<Self, ArithError> {
+        self.checked_add(*rhs).ok_or(ArithError::Overflow)
+    }
+
+    fn sub(&self, rhs: &Self) -> Result<Self, ArithError> {
+        self.checked_sub(*rhs).ok_or(ArithError::Overflow)
+    }
+
+    fn mul(&self, rhs: &Self) -> Result<Self, ArithError> {
+        self.checked_mul(*rhs).ok_or(ArithError::Overflow)
+    }
+
+    fn div(&self, rhs: &Self) -> Result<Self, ArithError> {
+        if *rhs == 0 {
+            return Err(ArithError::DivisionByZero);
+        }
+        // `i64::MIN / -1`
+        self.checked_div(*rhs).ok_or(ArithError::Overflow)
+    }
+
+    fn rem(&self, rhs: &Self) -> Result<Self, ArithError> {
+        if *rhs == 0 {
+            return Err(ArithError::DivisionByZero);
+        }
+        self.checked_rem(*rhs).ok_or(ArithError::Overflow)
+    }
+
+    fn pow(&self, exp: &Self) -> Result<Self, ArithError> {
+        if *exp < 0 {
+            return Err(ArithError::NegativeExponent);
+        }
+        let exp = u32::try_from(*exp).map_err(|_| ArithError::Overflow)?;
+
+        self.checked_pow(exp).ok_or(ArithError::Overflow)
+    }
+
+    fn abs(&self) -> Result<Self, ArithError> {
+        self.checked_abs().ok_or(ArithError::Overflow)
+    }
+
+    fn is_finite(&self) -> bool {
+        true
+    }
+
+    fn repr(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+impl IntOperand for i64 {
+    fn gcd(&self, rhs: &Self) -> Result<Self, ArithError> {
+        // `gcd(i64::MIN, 0)` is `2 ** 63`
+        i64::try_from(self.unsigned_abs().gcd(&rhs.unsigned_abs()))
+            .map_err(|_| ArithError::Overflow)
+    }
+
+    fn bit_and(&self, rhs: &Self) -> Result<Self, ArithError> {
+        Ok(self & rhs)
+    }
+
+    fn bit_or(&self, rhs: &Self) -> Result<Self, ArithError> {
+        Ok(self | rhs)
+    }
+
+    fn bit_xor(&self, rhs: &Self) -> Result<Self, ArithError> {
+        Ok(self ^ rhs)
+    }
+
+    // `checked_shl` only checks the shift count, so bits shifted out are checked here,
+    // as `1 << 63` doesn't fit in `i64`
+    fn shl(&self, rhs: &Self) -> Result<Self, ArithError> {
+        let rhs = u32::try_from(*rhs).map_err(|_| ArithError::Overflow)?;
+        let res = self.checked_shl(rhs).ok_or(ArithError::Overflow)?;
+        if res >> rhs != *self {
+            return Err(ArithError::Overflow);
+        }
+
+        Ok(res)
+    }
+
+    // Arithmetic shift, so the sign is kept
+    fn shr(&self, rhs: &Self) -> Result<Self, ArithError> {
+        let rhs = u32::try_from(*rhs).map_err(|_| ArithError::Overflow)?;
+        self.checked_shr(rhs).ok_or(ArithError::Overflow)
+    }
+
+    fn not(&self) -> Result<Self, ArithError> {
+        Ok(!self)
+    }
+}
+
+impl Operand for f64 {
+    fn add(&self, rhs: &Self) -> Result<Self, ArithError> {
+        Ok(self + rhs)
+    }
+
+    fn sub(&self, rhs: &Self) -> Result<Self, ArithError> {
+        Ok(self - rhs)
+    }
+
+    fn mul(&self, rhs: &Self) -> Result<Self, ArithError> {
+        Ok(self * rhs)
+    }
+
+    fn div(&self, rhs: &Self) -> Result<Self, ArithError> {
+        Ok(self / rhs)
+    }
+
+    fn rem(&self, rhs: &Self) -> Result<Self, ArithError> {
+        Ok(self % rhs)
+    }
+
+    fn pow(&self, exp: &Self) -> Result<Self, ArithError> {
+        Ok(self.powf(*exp))
+    }
+
+    fn abs(&self) -> Result<Self, ArithError> {
+        Ok(f64::abs(*self))
+    }
+
+    fn is_finite(&self) -> bool {
+        f64::is_finite(*self)
+    }
+
+    fn repr(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+impl Operand for BigInt {
+    fn add(&self, rhs: &Self) -> Result<Self, ArithError> {
+        Ok(self + rhs)
+    }
+
+    fn sub(&self, rhs: &Self) -> Result<Self, ArithError> {
+        Ok(self - rhs)
+    }
+
+    fn mul(&self, rhs: &Self) -> Result<Self, ArithError> {
+        if self.bits() + rhs.bits() > MAX_BIG_BITS {
+            return Err(ArithError::Overflow);
+        }
+        Ok(self * rhs)
+    }
+
+    fn div(&self, rhs: &Self) -> Result<Self, ArithError> {
+        if rhs.is_zero() {
+            return Err(ArithError::DivisionByZero);
+        }
+        Ok(self / rhs)
+    }
+
+    fn rem(&self, rhs: &Self) -> Result<Self, ArithError> {
+        if rhs.is_zero() {
+            return Err(ArithError::DivisionByZero);
+        }
+        Ok(self % rhs)
+    }
+
+    fn pow(&self, exp: &Self) -> Result<Self, ArithError> {
+        if exp.is_negative() {
+            return Err(ArithError::NegativeExponent);
+        }
+        let exp = u32::try_from(exp).map_err(|_| ArithError::Overflow)?;
+        // The result has at least `exp` times the bits of `self` without the highest one,
+        // so `0`, `1` and `-1` can have any exponent
+        if (self.bits().saturating_sub(1)).saturating_mul(exp.into()) > MAX_BIG_BITS {
+            return Err(ArithError::Overflow);
+        }
+
+        Ok(Pow::pow(self, exp))
+    }
+
+    fn abs(&self) -> Result<Self, ArithError> {
+        Ok(Signed::abs(self))
+    }
+
+    fn is_finite(&self) -> bool {
+        true
+    }
+
+    fn repr(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl IntOperand for BigInt {
+    fn gcd(&self, rhs: &Self) -> Result<Self, ArithError> {
+        Ok(Integer::gcd(self, rhs))
+    }
+
+    // Big integers behave as if they were in two's complement with infinite sign bits
+
+    fn bit_and(&self, rhs: &Self) -> Result<Self, ArithError> {
+        Ok(self & rhs)
+    }
+
+    fn bit_or(&self, rhs: &Self) -> Result<Self, ArithError> {
+        Ok(self | rhs)
+    }
+
+    fn bit_xor(&self, rhs: &Self) -> Result<Self, ArithError> {
+        Ok(self ^ rhs)
+    }
+
+    fn shl(&self, rhs: &Self) -> Result<Self, ArithError> {
+        let rhs = u32::try_from(rhs).map_err(|_| ArithError::Overflow)?;
+        // Same limit as for `pow`, but `0` can be shifted by anything
+        if !self.is_zero() && self.bits() + u64::from(rhs) > MAX_BIG_BITS {
+            return Err(ArithError::Overflow);
+        }
+        Ok(self << rhs)
+    }
+
+    fn shr(&self, rhs: &Self) -> Result<Self, ArithError> {
+        let rhs = u32::try_from(rhs).map_err(|_| ArithError::Overflow)?;
+        Ok(self >> rhs)
+    }
+
+    fn not(&self) -> Result<Self, ArithError> {
+        Ok(!self)
+    }
+}
+
+impl Operand for BigDecimal {
+    fn add(&self, rhs: &Self) -> Result<Self, ArithError> {
+        Ok(self + rhs)
+    }
+
+    fn sub(&self, rhs: &Self) -> Result<Self, ArithError> {
+        Ok(self - rhs)
+    }
+
+    fn mul(&self, rhs: &Self) -> Result<Self, ArithError> {
+        // Same limit as for `pow`
+        let (lhs_digits, lhs_scale) = self.as_bigint_and_scale();
+        let (rhs_digits, rhs_scale) = rhs.as_bigint_and_scale();
+        if lhs_digits.bits() + rhs_digits.bits() > MAX_BIG_BITS
+            || lhs_scale.unsigned_abs() + rhs_scale.unsigned_abs() > MAX_BIG_BITS
+        {
+            return Err(ArithError::Overflow);
+        }
+        Ok(self * rhs)
+    }
+
+    fn div(&self, rhs: &Self) -> Result<Self, ArithError> {
+        if rhs.is_zero() {
+            return Err(ArithError::DivisionByZero);
+        }
+        Ok(self / rhs)
+    }
+
+    fn rem(&self, rhs: &Self) -> Result<Self, ArithError> {
+        if rhs.is_zero() {
+            return Err(ArithError::DivisionByZero);
+        }
+        Ok(self % rhs)
+    }
+
+    fn pow(&self, exp: &Self) -> Result<Self, ArithError> {
+        // Non-negative integer exponents are exact, negative ones are rounded
+        // to 100 significant digits and fractional ones are calculated with floats
+        if exp.is_integer() {
+            let exp = exp.to_i64().ok_or(ArithError::Overflow)?;
+            if exp < 0 && self.is_zero() {
+                return Err(ArithError::DivisionByZero);
+            }
+            return match u64::try_from(exp) {
+                Ok(exp) => big_decimal_pow(self, exp),
+                Err(_) => Ok(self.powi(exp)),
+            };
+        }
+
+        let res = self
+            .to_f64()
+            .unwrap_or(f64::NAN)
+            .powf(exp.to_f64().unwrap_or(f64::NAN));
+        big_decimal_from_f64(res).ok_or(ArithError::NonFinite)
+    }
+
+    fn abs(&self) -> Result<Self, ArithError> {
+        Ok(BigDecimal::abs(self))
+    }
+
+    fn is_finite(&self) -> bool {
+        true
+    }
+
+    fn repr(&self) -> String {
+        self.to_string()
+    }
+}
+
+// Square-and-multiply, because `BigDecimal::powi()` rounds the result. The limit is the same
+// as for `BigInt`, and the number of decimal places is limited too, because they are printed
+fn big_decimal_pow(base: &BigDecimal, mut exp: u64) -> Result<BigDecimal, ArithError> {
+    let (digits, scale) = base.normalized().as_bigint_and_exponent();
+    if digits.bits().saturating_sub(1).saturating_mul(exp) > MAX_BIG_BITS
+        || scale.unsigned_abs().saturating_mul(exp) > MAX_BIG_BITS
+    {
+        return Err(ArithError::Overflow);
+    }
+
+    let (mut base, mut res) = (base.clone(), BigDecimal::one());
+    while exp > 0 {
+        if exp & 1 == 1 {
+            res = &res * &base;
+        }
+        exp >>= 1;
+        if exp > 0 {
+            base = &base * &base;
+        }
+    }
+
+    Ok(res)
+}
+
+/// Converts the float to the shortest decimal that is converted back to the same float,
+/// so `0.1` is `0.1` and not `0.1000000000000000055511151231257827021181583404541015625`.
+/// `inf` and `NaN` can't be converted.
+pub fn big_decimal_from_f64(val: f64) -> Option<BigDecimal> {
+    BigDecimal::from_str(&val.to_string()).ok()
+}
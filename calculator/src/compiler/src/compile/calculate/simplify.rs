@@ -0,0 +1,304 @@
+use std::{
+    collections::HashMap,
+    mem::{discriminant, Discriminant},
+};
+
+use num_traits::{One as _, Zero as _};
+
+use super::{Env, Program};
+use crate::{
+    ast_lowering::ast::{
+        Ast, BinOp, BinOpKind, Call, Cond, Expr, ExprKind, Let, Lit, LiteralKind, Stmt, UnOp,
+        UnOpKind,
+    },
+    compile::{CompileOptions, IntDivision},
+    errors::{diagnostic::DiagnosticCtxt, emitter::SilentEmitter},
+    helpers::stack::ensure_sufficient_stack,
+    span::Span,
+};
+
+type Type = Discriminant<LiteralKind>;
+
+// Operand of a binary operation
+enum Side {
+    Lhs,
+    Rhs,
+}
+
+/// Folds subexpressions whose operands are literals and removes operations that don't change
+/// their operand, like `x * 1`. The simplified program has the same result and diagnostics:
+/// subexpressions that would report an error or a warning are not folded,
+/// so they are reported when the program is calculated.
+pub struct Simplifier {
+    options: CompileOptions,
+    // Diagnostics of the folded subexpressions, they are never shown
+    diag_ctxt: DiagnosticCtxt,
+    // Types of the variables that are defined by the simplified program
+    types: HashMap<String, Type>,
+}
+
+impl Simplifier {
+    /// Variables of `env` are defined before the program, so their types are known
+    pub fn new(options: CompileOptions, env: &Env) -> Self {
+        let types = env
+            .names()
+            .filter_map(|name| {
+                let ty = discriminant(&env.get(&name)?.kind);
+                Some((name, ty))
+            })
+            .collect();
+
+        Self {
+            options,
+            diag_ctxt: DiagnosticCtxt::new(Box::new(SilentEmitter)),
+            types,
+        }
+    }
+
+    pub fn simplify(&mut self, ast: Ast) -> Ast {
+        match ast {
+            Ast::Stmts(stmts) => Ast::Stmts(
+                stmts
+                    .into_iter()
+                    .map(|stmt| self.simplify_stmt(stmt))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn simplify_stmt(&mut self, stmt: Stmt) -> Stmt {
+        match stmt {
+            Stmt::Expr(expr) => Stmt::Expr(self.simplify_expr(expr)),
+            Stmt::Let(Let { ident, expr }) => {
+                let expr = self.simplify_expr(expr);
+                match self.type_of(&expr) {
+                    Some(ty) => self.types.insert(ident.name.clone(), ty),
+                    None => self.types.remove(&ident.name),
+                };
+
+                Stmt::Let(Let { ident, expr })
+            }
+        }
+    }
+
+    fn simplify_expr(&mut self, Expr { kind, span }: Expr) -> Expr {
+        ensure_sufficient_stack(|| {
+            let kind = match kind {
+                ExprKind::Lit(_) | ExprKind::Ident(_) => kind,
+                ExprKind::BinOp(binop) => return self.simplify_binop(binop, span),
+                ExprKind::UnOp(UnOp { expr, op }) => {
+                    let expr = self.simplify_expr(*expr);
+                    // `+x` is `x`, but `+` can't be applied to booleans
+                    if op == UnOpKind::Pos && self.type_of(&expr).is_some_and(is_number) {
+                        return Expr { span, ..expr };
+                    }
+
+                    ExprKind::UnOp(UnOp {
+                        expr: Box::new(expr),
+                        op,
+                    })
+                }
+                ExprKind::Call(Call { ident, args }) => ExprKind::Call(Call {
+                    ident,
+                    args: args
+                        .into_iter()
+                        .map(|arg| self.simplify_expr(arg))
+                        .collect(),
+                }),
+                ExprKind::Cond(Cond {
+                    cond,
+                    if_true,
+                    if_false,
+                }) => {
+                    let cond = self.simplify_expr(*cond);
+
+                    // Other branch is never calculated, so we can just remove it
+                    match cond.kind {
+                        ExprKind::Lit(Lit {
+                            kind: LiteralKind::Bool { val: true },
+                        }) => return self.simplify_expr(*if_true),
+                        ExprKind::Lit(Lit {
+                            kind: LiteralKind::Bool { val: false },
+                        }) => return self.simplify_expr(*if_false),
+                        _ => ExprKind::Cond(Cond {
+                            cond: Box::new(cond),
+                            if_true: Box::new(self.simplify_expr(*if_true)),
+                            if_false: Box::new(self.simplify_expr(*if_false)),
+                        }),
+                    }
+                }
+            };
+
+            self.fold(Expr { kind, span })
+        })
+    }
+
+    fn simplify_binop(&mut self, BinOp { lhs, rhs, op }: BinOp, span: Span) -> Expr {
+        let lhs = self.simplify_expr(*lhs);
+
+        // Right operand of `&&` and `||` isn't calculated if the left one decides the result
+        if let ExprKind::Lit(Lit {
+            kind: LiteralKind::Bool { val },
+        }) = lhs.kind
+        {
+            if (op == BinOpKind::And && !val) || (op == BinOpKind::Or && val) {
+                return Expr { span, ..lhs };
+            }
+        }
+
+        let rhs = self.simplify_expr(*rhs);
+        match self.remove_identity(&lhs, &rhs, op) {
+            Some(Side::Lhs) => return Expr { span, ..lhs },
+            Some(Side::Rhs) => return Expr { span, ..rhs },
+            None => {}
+        }
+
+        self.fold(Expr {
+            kind: ExprKind::BinOp(BinOp {
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+                op,
+            }),
+            span,
+        })
+    }
+
+    // `x * 1`, `1 * x`, `x / 1`, `x ** 1`, `x + 0`, `0 + x`, `x - 0`, `true && x`,
+    // `x && true`, `false || x` and `x || false` are `x`, if `x` has the type of the literal,
+    // so there is no type conversion or type error that we would remove.
+    // Returns the operand that is left.
+    fn remove_identity(&self, lhs: &Expr, rhs: &Expr, op: BinOpKind) -> Option<Side> {
+        let is_identity = |x: &Expr, lit: &Expr, check: fn(&LiteralKind) -> bool| match &lit.kind {
+            ExprKind::Lit(Lit { kind }) => {
+                check(kind) && self.type_of(x) == Some(discriminant(kind))
+            }
+            _ => false,
+        };
+        // `x / 1` is a float if integers are divided into floats
+        let is_div_identity = match self.options.int_div {
+            IntDivision::Truncate => is_one,
+            IntDivision::Float => is_float_one,
+        };
+
+        match op {
+            BinOpKind::Mul if is_identity(rhs, lhs, is_one) => Some(Side::Rhs),
+            BinOpKind::Mul | BinOpKind::Pow if is_identity(lhs, rhs, is_one) => Some(Side::Lhs),
+            BinOpKind::Div if is_identity(lhs, rhs, is_div_identity) => Some(Side::Lhs),
+            BinOpKind::Add if is_identity(rhs, lhs, is_int_zero) => Some(Side::Rhs),
+            BinOpKind::Add | BinOpKind::Sub if is_identity(lhs, rhs, is_int_zero) => {
+                Some(Side::Lhs)
+            }
+            BinOpKind::And if is_identity(rhs, lhs, is_true) => Some(Side::Rhs),
+            BinOpKind::And if is_identity(lhs, rhs, is_true) => Some(Side::Lhs),
+            BinOpKind::Or if is_identity(rhs, lhs, is_false) => Some(Side::Rhs),
+            BinOpKind::Or if is_identity(lhs, rhs, is_false) => Some(Side::Lhs),
+            _ => None,
+        }
+    }
+
+    // Calculates the expression if all its operands are literals
+    // and it doesn't report anything
+    fn fold(&self, expr: Expr) -> Expr {
+        let is_const = match &expr.kind {
+            ExprKind::BinOp(BinOp { lhs, rhs, .. }) => is_lit(lhs) && is_lit(rhs),
+            ExprKind::UnOp(UnOp { expr, .. }) => is_lit(expr),
+            ExprKind::Call(Call { args, .. }) => args.iter().all(is_lit),
+            ExprKind::Lit(_) | ExprKind::Ident(_) | ExprKind::Cond(_) => false,
+        };
+        if !is_const {
+            return expr;
+        }
+
+        let (errs, warns) = (self.diag_ctxt.err_count(), self.diag_ctxt.warn_count());
+        let program = Program::new(
+            Ast::Stmts(Vec::new()),
+            String::new(),
+            &self.diag_ctxt,
+            self.options,
+        );
+
+        match program.compile_expr(&expr, &Env::new()) {
+            Ok(lit)
+                if self.diag_ctxt.err_count() == errs && self.diag_ctxt.warn_count() == warns =>
+            {
+                Expr {
+                    kind: ExprKind::Lit(lit),
+                    span: expr.span,
+                }
+            }
+            _ => expr,
+        }
+    }
+
+    // Returns the type of the expression, if we know it without calculating it
+    fn type_of(&self, expr: &Expr) -> Option<Type> {
+        ensure_sufficient_stack(|| {
+            match &expr.kind {
+                ExprKind::Lit(Lit { kind }) => Some(discriminant(kind)),
+                ExprKind::Ident(ident) => self.types.get(&ident.name).copied(),
+                ExprKind::UnOp(UnOp { op, .. }) if *op == UnOpKind::Not => Some(bool_type()),
+                ExprKind::UnOp(UnOp { expr, .. }) => self.type_of(expr),
+                ExprKind::BinOp(BinOp { op, .. })
+                    if op.is_comparison() || matches!(op, BinOpKind::And | BinOpKind::Or) =>
+                {
+                    Some(bool_type())
+                }
+                // Integers can be divided into a float
+                ExprKind::BinOp(BinOp { op, .. })
+                    if *op == BinOpKind::Div && self.options.int_div == IntDivision::Float =>
+                {
+                    None
+                }
+                // Mixed operands are converted, but we don't know to what
+                ExprKind::BinOp(BinOp { lhs, rhs, .. }) => {
+                    let ty = self.type_of(lhs)?;
+                    (self.type_of(rhs)? == ty).then_some(ty)
+                }
+                ExprKind::Call(_) | ExprKind::Cond(_) => None,
+            }
+        })
+    }
+}
+
+fn is_lit(expr: &Expr) -> bool {
+    matches!(expr.kind, ExprKind::Lit(_))
+}
+
+fn bool_type() -> Type {
+    discriminant(&LiteralKind::Bool { val: false })
+}
+
+fn is_number(ty: Type) -> bool {
+    ty != bool_type()
+}
+
+// Big decimals are not used as identities, because their operations can change
+// the scale of the result, so `1.50 * 1.0` is shown as `1.500`
+fn is_one(val: &LiteralKind) -> bool {
+    match val {
+        LiteralKind::Int { val } => *val == 1,
+        LiteralKind::BigInt { val } => val.is_one(),
+        _ => is_float_one(val),
+    }
+}
+
+fn is_float_one(val: &LiteralKind) -> bool {
+    matches!(val, LiteralKind::Float { val } if *val == 1.0)
+}
+
+// Floats are not used as identities, because `-0.0 + 0.0` is `0.0`
+fn is_int_zero(val: &LiteralKind) -> bool {
+    match val {
+        LiteralKind::Int { val } => *val == 0,
+        LiteralKind::BigInt { val } => val.is_zero(),
+        _ => false,
+    }
+}
+
+fn is_true(val: &LiteralKind) -> bool {
+    matches!(val, LiteralKind::Bool { val: true })
+}
+
+fn is_false(val: &LiteralKind) -> bool {
+    matches!(val, LiteralKind::Bool { val: false })
+}
@@ -1,23 +1,32 @@
+use std::{
+    str::FromStr as _,
+    time::{Duration, Instant},
+};
+
+use bigdecimal::BigDecimal;
+use num_bigint::BigInt;
+
 use crate::{
     ast_lowering::ast::{Lit, LiteralKind},
+    compile::{CompileOptions, IntDivision, MixingPolicy, NumMode},
     errors::{
-        diagnostic::{DiagnosticCtxt, DiagnosticMsg},
-        emitter::Emitter,
+        diagnostic::{Diagnostic, DiagnosticCtxt},
+        emitter::{Emitter, SilentEmitter},
     },
 };
 
-use super::Program;
+use super::{Env, Program};
 
 #[derive(Debug)]
 struct MockEmitter;
 
 impl Emitter for MockEmitter {
-    fn emit_diag(&self, diag_msg: &DiagnosticMsg) {
-        panic!("{:?}", diag_msg)
+    fn emit_diag(&self, diag: &Diagnostic) {
+        panic!("{:?}", diag.get_msg())
     }
 
-    fn emit_warn(&self, diag_msg: &DiagnosticMsg) {
-        panic!("{:?}", diag_msg)
+    fn emit_warn(&self, diag: &Diagnostic) {
+        panic!("{:?}", diag.get_msg())
     }
 }
 
@@ -36,3 +45,748 @@ fn test_calculate() {
         }
     );
 }
+
+#[test]
+fn test_calculate_variables() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let res = Program::from_source(
+        "let x = 2 * 3; let y = x - 1; x * y",
+        "".to_string(),
+        &diag_ctxt,
+    )
+    .unwrap()
+    .compile()
+    .unwrap();
+
+    assert_eq!(
+        res,
+        Lit {
+            kind: LiteralKind::Int { val: 30 }
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "cannot find variable `y` in this scope")]
+fn test_undefined_variable() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("let x = 1; x + y", "".to_string(), &diag_ctxt)
+        .unwrap()
+        .compile();
+}
+
+#[test]
+#[should_panic(expected = "variable `x` is already defined")]
+fn test_redefined_variable() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("let x = 1; let x = 2", "".to_string(), &diag_ctxt)
+        .unwrap()
+        .compile();
+}
+
+#[test]
+fn test_calculate_unop() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let compile = |src| {
+        Program::from_source(src, "".to_string(), &diag_ctxt)
+            .unwrap()
+            .compile()
+            .unwrap()
+    };
+
+    assert_eq!(
+        compile("2 * -3 + +1"),
+        Lit {
+            kind: LiteralKind::Int { val: -5 }
+        }
+    );
+    assert_eq!(
+        compile("-(1.5 - 4.0)"),
+        Lit {
+            kind: LiteralKind::Float { val: 2.5 }
+        }
+    );
+}
+
+#[test]
+fn test_calculate_pow() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let compile = |src| {
+        Program::from_source(src, "".to_string(), &diag_ctxt)
+            .unwrap()
+            .compile()
+            .unwrap()
+    };
+
+    assert_eq!(
+        compile("2 ** 3 ** 2"),
+        Lit {
+            kind: LiteralKind::Int { val: 512 }
+        }
+    );
+    assert_eq!(
+        compile("4.0 ** -0.5"),
+        Lit {
+            kind: LiteralKind::Float { val: 0.5 }
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "attempt to compute `2 ** 63`, which would overflow")]
+fn test_pow_overflow() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("2 ** 63", "".to_string(), &diag_ctxt)
+        .unwrap()
+        .compile();
+}
+
+#[test]
+#[should_panic(expected = "attempt to compute `1 / 0`, which is a division by zero")]
+fn test_division_by_zero() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("1 / (2 - 2)", "".to_string(), &diag_ctxt)
+        .unwrap()
+        .compile();
+}
+
+#[test]
+#[should_panic(expected = "attempt to compute `9223372036854775807 + 1`, which would overflow")]
+fn test_add_overflow() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("9223372036854775807 + 1", "".to_string(), &diag_ctxt)
+        .unwrap()
+        .compile();
+}
+
+#[test]
+#[should_panic(expected = "result of `1.0 / 0.0` is `inf`")]
+fn test_float_division_by_zero() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("1.0 / 0.0", "".to_string(), &diag_ctxt)
+        .unwrap()
+        .compile();
+}
+
+#[test]
+fn test_calculate_builtins() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let compile = |src| {
+        Program::from_source(src, "".to_string(), &diag_ctxt)
+            .unwrap()
+            .compile()
+            .unwrap()
+    };
+
+    assert_eq!(
+        compile("max(1, 7, 2) - abs(-3) * gcd(12, 18)"),
+        Lit {
+            kind: LiteralKind::Int { val: -11 }
+        }
+    );
+    assert_eq!(
+        compile("sqrt(16.0) + min(2.5, abs(-0.5))"),
+        Lit {
+            kind: LiteralKind::Float { val: 4.5 }
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "cannot find function `foo` in this scope")]
+fn test_unknown_function() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("1 + foo(2)", "".to_string(), &diag_ctxt)
+        .unwrap()
+        .compile();
+}
+
+#[test]
+#[should_panic(expected = "function `atan2` takes 2 arguments, but got 1")]
+fn test_wrong_arg_count() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("atan2(1.0)", "".to_string(), &diag_ctxt)
+        .unwrap()
+        .compile();
+}
+
+#[test]
+#[should_panic(
+    expected = "function `gcd` expected an argument of type `Int`, found `Float { val: 2.5 }`"
+)]
+fn test_mismatched_arg_type() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("gcd(10, 2.5)", "".to_string(), &diag_ctxt)
+        .unwrap()
+        .compile();
+}
+
+#[test]
+#[should_panic(expected = "result of `sqrt(-1.0)` is `NaN`")]
+fn test_builtin_non_finite_result() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("sqrt(-1.0)", "".to_string(), &diag_ctxt)
+        .unwrap()
+        .compile();
+}
+
+#[test]
+fn test_calculate_constants() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let res = Program::from_source("let r = 2.0; pi * r - e", "".to_string(), &diag_ctxt)
+        .unwrap()
+        .compile()
+        .unwrap();
+
+    assert_eq!(
+        res,
+        Lit {
+            kind: LiteralKind::Float {
+                val: std::f64::consts::PI * 2.0 - std::f64::consts::E
+            }
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "cannot redefine constant `pi`")]
+fn test_redefined_constant() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("let pi = 3", "".to_string(), &diag_ctxt);
+}
+
+#[test]
+#[should_panic(expected = "cannot find variable `x` in this scope")]
+fn test_variable_used_in_its_definition() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("let x = x + 1", "".to_string(), &diag_ctxt);
+}
+
+#[test]
+fn test_calculate_i64() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let res = Program::from_source("3000000000 * 3000000000", "".to_string(), &diag_ctxt)
+        .unwrap()
+        .compile()
+        .unwrap();
+
+    assert_eq!(
+        res,
+        Lit {
+            kind: LiteralKind::Int {
+                val: 9_000_000_000_000_000_000
+            }
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "failed to parse int `99999999999999999999`")]
+fn test_too_large_int() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("99999999999999999999", "".to_string(), &diag_ctxt);
+}
+
+#[test]
+#[should_panic(expected = "failed to parse float `1e400`: number too large to fit in target type")]
+fn test_too_large_float() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("1e400", "".to_string(), &diag_ctxt);
+}
+
+fn compile_with<'a>(
+    src: &str,
+    diag_ctxt: &'a DiagnosticCtxt,
+    options: CompileOptions,
+) -> Result<Lit, Diagnostic<'a>> {
+    Program::from_source_in(src, "".to_string(), diag_ctxt, &Env::new(), options)?.compile()
+}
+
+fn compile_big<'a>(src: &str, diag_ctxt: &'a DiagnosticCtxt) -> Result<Lit, Diagnostic<'a>> {
+    let options = CompileOptions {
+        num_mode: NumMode::Big,
+        ..Default::default()
+    };
+
+    compile_with(src, diag_ctxt, options)
+}
+
+#[test]
+fn test_calculate_big_numbers() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+
+    assert_eq!(
+        compile_big("99999999999999999999 * 10 + 2 ** 70", &diag_ctxt).unwrap(),
+        Lit {
+            kind: LiteralKind::BigInt {
+                val: BigInt::from_str("2180591620717411303414").unwrap()
+            }
+        }
+    );
+    assert_eq!(
+        compile_big("0.1 + 0.2 - max(1.0, 0.5) / 4.0", &diag_ctxt).unwrap(),
+        Lit {
+            kind: LiteralKind::BigDecimal {
+                val: BigDecimal::from_str("0.05").unwrap()
+            }
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "attempt to compute `1 / 0`, which is a division by zero")]
+fn test_big_division_by_zero() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = compile_big("1 / (2 - 2)", &diag_ctxt);
+}
+
+#[test]
+fn test_big_pow() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(SilentEmitter));
+
+    // Integer exponents of decimals are exact, not rounded to 100 digits
+    assert_eq!(
+        compile_big("1.1 ** 200", &diag_ctxt).unwrap().kind,
+        LiteralKind::BigDecimal {
+            val: BigDecimal::from_str(
+                "189905276.46046182421218204639541163405858322400098778481272514561037626461679\
+                 89140750662066593328455813588180523840104494943586836790591302000591144234006\
+                 2387227375955664576836341689587626164144676307968892001"
+            )
+            .unwrap()
+        }
+    );
+    assert_eq!(
+        compile_big("1 ** 4000000000 + 0.1 ** 3", &diag_ctxt)
+            .unwrap()
+            .kind,
+        LiteralKind::BigDecimal {
+            val: BigDecimal::from_str("1.001").unwrap()
+        }
+    );
+
+    // Results that are too large overflow at once instead of running out of memory
+    let start = Instant::now();
+    for src in ["2 ** 4000000000", "1.1 ** 4000000000", "0.5 ** 4000000000"] {
+        assert_eq!(
+            compile_big(src, &diag_ctxt).unwrap_err().get_msg(),
+            format!("attempt to compute `{src}`, which would overflow")
+        );
+    }
+    // Products are limited too, so squaring can't grow the result without end
+    for src in [
+        "let x = 2 ** 1000000; x * x",
+        "let x = 0.1 ** 600000; x * x",
+    ] {
+        assert!(compile_big(src, &diag_ctxt)
+            .unwrap_err()
+            .get_msg()
+            .ends_with("which would overflow"));
+    }
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+#[should_panic(expected = "constant `inf` is not supported with big numbers")]
+fn test_big_inf() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = compile_big("1.0 + inf", &diag_ctxt);
+}
+
+#[test]
+fn test_silent_mixing() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let options = CompileOptions {
+        mixing: MixingPolicy::Silent,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        compile_with("max(1, 0.5) + 2 * 2.5", &diag_ctxt, options).unwrap(),
+        Lit {
+            kind: LiteralKind::Float { val: 6.0 }
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "mismatched types: `Int { val: 2 }` and `Float { val: 2.5 }`")]
+fn test_strict_mixing() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let options = CompileOptions {
+        mixing: MixingPolicy::Strict,
+        ..Default::default()
+    };
+
+    let _ = compile_with("1 + 2 * 2.5", &diag_ctxt, options);
+}
+
+#[test]
+fn test_int_division() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let float_div = CompileOptions {
+        int_div: IntDivision::Float,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        compile_with("-7 / 2", &diag_ctxt, CompileOptions::default()).unwrap(),
+        Lit {
+            kind: LiteralKind::Int { val: -3 }
+        }
+    );
+    assert_eq!(
+        compile_with("-7 / 2", &diag_ctxt, float_div).unwrap(),
+        Lit {
+            kind: LiteralKind::Float { val: -3.5 }
+        }
+    );
+    // Other operations are still integer
+    assert_eq!(
+        compile_with("7 % 2 * 3", &diag_ctxt, float_div).unwrap(),
+        Lit {
+            kind: LiteralKind::Int { val: 3 }
+        }
+    );
+    assert_eq!(
+        compile_with(
+            "7 / 2",
+            &diag_ctxt,
+            CompileOptions {
+                num_mode: NumMode::Big,
+                ..float_div
+            }
+        )
+        .unwrap(),
+        Lit {
+            kind: LiteralKind::BigDecimal {
+                val: BigDecimal::from_str("3.5").unwrap()
+            }
+        }
+    );
+}
+
+#[test]
+fn test_number_literals() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let compile = |src| {
+        Program::from_source(src, "".to_string(), &diag_ctxt)
+            .unwrap()
+            .compile()
+            .unwrap()
+    };
+
+    assert_eq!(
+        compile("0xFF - 0b1010 + 0o17 + 1_000_000"),
+        Lit {
+            kind: LiteralKind::Int { val: 1_000_260 }
+        }
+    );
+    assert_eq!(
+        compile("1.5e-3 * 2E2 + 1.e1"),
+        Lit {
+            kind: LiteralKind::Float { val: 10.3 }
+        }
+    );
+    assert_eq!(
+        compile_big("0xFFFF_FFFF_FFFF_FFFF_F", &diag_ctxt).unwrap(),
+        Lit {
+            kind: LiteralKind::BigInt {
+                val: BigInt::from_str("295147905179352825855").unwrap()
+            }
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "invalid digit `2` in binary number literal")]
+fn test_invalid_digit() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("0b1012", "".to_string(), &diag_ctxt);
+}
+
+#[test]
+#[should_panic(expected = "hexadecimal number literal `0x_` has no digits")]
+fn test_empty_number_literal() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("0x_ + 1", "".to_string(), &diag_ctxt);
+}
+
+#[test]
+#[should_panic(expected = "expected at least one digit in exponent of `1.5e-`")]
+fn test_empty_exponent() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("1.5e-", "".to_string(), &diag_ctxt);
+}
+
+#[test]
+#[should_panic(expected = "octal number literal `0o1.5` can't have a fractional part")]
+fn test_fractional_prefixed_literal() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("0o1.5", "".to_string(), &diag_ctxt);
+}
+
+#[test]
+fn test_bitwise_operators() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let compile = |src| {
+        Program::from_source(src, "".to_string(), &diag_ctxt)
+            .unwrap()
+            .compile()
+            .unwrap()
+    };
+
+    assert_eq!(
+        compile("0xF0 | 0x0F & 0b1010 ^ 1 << 2"),
+        Lit {
+            kind: LiteralKind::Int {
+                val: 0xF0 | (0x0F & (0b1010 ^ (1 << 2)))
+            }
+        }
+    );
+    assert_eq!(
+        compile("~0 + (-16 >> 2)"),
+        Lit {
+            kind: LiteralKind::Int { val: -5 }
+        }
+    );
+    assert_eq!(
+        compile_big("1 << 100 >> 98 | ~-8", &diag_ctxt).unwrap(),
+        Lit {
+            kind: LiteralKind::BigInt {
+                val: BigInt::from(7)
+            }
+        }
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "operator `&` expected an operand of type `Int`, found `Float { val: 2.5 }`"
+)]
+fn test_bitwise_float_operand() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("1 & 2.5", "".to_string(), &diag_ctxt)
+        .unwrap()
+        .compile();
+}
+
+#[test]
+#[should_panic(
+    expected = "operator `~` expected an operand of type `Int`, found `Float { val: 1.0 }`"
+)]
+fn test_bitwise_not_float_operand() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("~1.0", "".to_string(), &diag_ctxt)
+        .unwrap()
+        .compile();
+}
+
+#[test]
+fn test_shift_overflow() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(SilentEmitter));
+    let compile = |src| {
+        Program::from_source(src, "".to_string(), &diag_ctxt)
+            .unwrap()
+            .compile()
+    };
+
+    for src in ["1 << 64", "1 << 63", "3 << 62"] {
+        assert_eq!(
+            compile(src).unwrap_err().get_msg(),
+            format!("attempt to compute `{src}`, which would overflow")
+        );
+    }
+    // Only the sign bit is left, so nothing is lost
+    assert_eq!(
+        compile("-1 << 63").unwrap().kind,
+        LiteralKind::Int { val: i64::MIN }
+    );
+    // Big integers don't lose bits, but their size is limited
+    assert_eq!(
+        compile_big("1 << 4000000000", &diag_ctxt)
+            .unwrap_err()
+            .get_msg(),
+        "attempt to compute `1 << 4000000000`, which would overflow"
+    );
+}
+
+#[test]
+fn test_comparisons_and_logic() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let compile = |src| {
+        Program::from_source(src, "".to_string(), &diag_ctxt)
+            .unwrap()
+            .compile()
+            .unwrap()
+    };
+    let bool = |val| Lit {
+        kind: LiteralKind::Bool { val },
+    };
+
+    assert_eq!(compile("1 < 2 && 2 <= 2 && 3 > 2 && 3 >= 4"), bool(false));
+    assert_eq!(compile("1.5 != 1.5 || (2 == 2) == true"), bool(true));
+    assert_eq!(compile("!(0b11 & 1 == 1)"), bool(false));
+    // The right operand is not calculated, so there is no division by zero
+    assert_eq!(compile("false && 1 / 0 == 0"), bool(false));
+    assert_eq!(compile("true || 1 / 0 == 0"), bool(true));
+    assert_eq!(
+        compile("let t = 30; t > 40 ? 2 : t > 25 ? 1 / 1 : 1 / 0"),
+        Lit {
+            kind: LiteralKind::Int { val: 1 }
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "expected a condition of type `Bool`, found `Int { val: 1 }`")]
+fn test_non_bool_condition() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("1 ? 2 : 3", "".to_string(), &diag_ctxt)
+        .unwrap()
+        .compile();
+}
+
+#[test]
+#[should_panic(
+    expected = "operator `&&` expected an operand of type `Bool`, found `Int { val: 2 }`"
+)]
+fn test_non_bool_logical_operand() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("true && 2", "".to_string(), &diag_ctxt)
+        .unwrap()
+        .compile();
+}
+
+#[test]
+#[should_panic(expected = "operator `*` expected a number, found `Bool { val: true }`")]
+fn test_bool_arithmetic() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("2 * (1 < 2)", "".to_string(), &diag_ctxt)
+        .unwrap()
+        .compile();
+}
+
+#[test]
+#[should_panic(expected = "mismatched types: `Int { val: 1 }` and `Bool { val: false }`")]
+fn test_compare_number_with_bool() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Program::from_source("1 != false", "".to_string(), &diag_ctxt)
+        .unwrap()
+        .compile();
+}
+
+fn simplify(src: &str, diag_ctxt: &DiagnosticCtxt) -> String {
+    Program::from_source(src, "".to_string(), diag_ctxt)
+        .unwrap()
+        .get_ast()
+        .to_string()
+}
+
+#[test]
+fn test_constant_folding() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+
+    assert_eq!(simplify("2 + 3 * 4", &diag_ctxt), "14");
+    assert_eq!(
+        simplify("let x = 2 * 3; x + 4 / 2", &diag_ctxt),
+        "let x = 6; x + 2"
+    );
+    assert_eq!(simplify("sqrt(16.0) * -(1.0 + 1.0)", &diag_ctxt), "-8.0");
+    assert_eq!(
+        simplify("let x = 1; x > 2 - 1 || 3 < 2 ? x : 0", &diag_ctxt),
+        "let x = 1; x > 1 ? x : 0"
+    );
+    assert_eq!(
+        simplify("let x = 1; false && x > 0", &diag_ctxt),
+        "let x = 1; false"
+    );
+}
+
+#[test]
+fn test_algebraic_identities() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+
+    assert_eq!(
+        simplify("let x = 5; (x + 0) * 1 - 0 + 1 * x ** 1 / 1", &diag_ctxt),
+        "let x = 5; x + x"
+    );
+    assert_eq!(
+        simplify(
+            "let x = 2.5; let b = x > 1.0; 1.0 * x + (true && b || false ? 1 : 0)",
+            &diag_ctxt
+        ),
+        "let x = 2.5; let b = x > 1.0; x + (b ? 1 : 0)"
+    );
+}
+
+#[test]
+fn test_identities_keep_types() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let options = CompileOptions {
+        mixing: MixingPolicy::Silent,
+        int_div: IntDivision::Float,
+        ..Default::default()
+    };
+    let program = Program::from_source_in(
+        "let x = 3; let y = x * 1.0 + x / 1; x + 0.0",
+        "".to_string(),
+        &diag_ctxt,
+        &Env::new(),
+        options,
+    )
+    .unwrap();
+
+    // `x` is an integer, so these operations convert it to a float
+    assert_eq!(
+        program.get_ast().to_string(),
+        "let x = 3; let y = x * 1.0 + x / 1; x + 0.0"
+    );
+    assert_eq!(
+        program.compile().unwrap(),
+        Lit {
+            kind: LiteralKind::Float { val: 3.0 }
+        }
+    );
+}
+
+#[test]
+#[should_panic(expected = "attempt to compute `1 / 0`, which is a division by zero")]
+fn test_folding_keeps_errors() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+
+    assert_eq!(simplify("2 * (1 / 0)", &diag_ctxt), "2 * (1 / 0)");
+    let _ = Program::from_source("2 * (1 / 0)", "".to_string(), &diag_ctxt)
+        .unwrap()
+        .compile();
+}
+
+#[test]
+fn test_simplified_source_parses_back() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let src = "let x = 2; let y = -x; (x - y) - (x - 1) * -y ** 2 ** x + (-x) ** 2 - ~x % 3 << 1";
+    let simplified = simplify(src, &diag_ctxt);
+
+    assert_eq!(
+        simplified,
+        "let x = 2; let y = -x; x - y - (x - 1) * -y ** 2 ** x + (-x) ** 2 - ~x % 3 << 1"
+    );
+    assert_eq!(simplify(&simplified, &diag_ctxt), simplified);
+}
+
+#[test]
+fn test_deeply_nested_program() {
+    // The test thread has a small stack, so every stage must grow it or avoid recursion,
+    // including dropping the program
+    let depth = 100_000;
+    let src = format!("let x = 1; {}x{}", "(x + ".repeat(depth), ")".repeat(depth));
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let program = Program::from_source(&src, "".to_string(), &diag_ctxt).unwrap();
+
+    assert_eq!(
+        program.compile().unwrap().kind,
+        LiteralKind::Int {
+            val: depth as i64 + 1
+        }
+    );
+}
@@ -0,0 +1,135 @@
+mod bytecode;
+#[cfg(test)]
+mod tests;
+
+use super::{CalcRes, Env, Program};
+use crate::{
+    ast_lowering::ast::{BinOpKind, Lit, LiteralKind},
+    compile::Compile,
+    span::Span,
+};
+pub use bytecode::{Chunk, Instr};
+
+/// Second backend of `Program`: the program is lowered to bytecode, which is executed
+/// on a stack machine. Unlike `Program::compile`, it doesn't use recursion, so the depth
+/// of expressions is not limited by the Rust stack. Results and diagnostics are the same.
+pub struct Vm<'p, 'a> {
+    program: &'p Program<'a>,
+    chunk: Chunk,
+}
+
+impl<'p, 'a> Compile for Vm<'p, 'a> {
+    type Ret = CalcRes<'a>;
+
+    fn compile(&self) -> Self::Ret {
+        self.run_in(&mut Env::new())
+    }
+}
+
+impl<'p, 'a> Vm<'p, 'a> {
+    pub fn new(program: &'p Program<'a>) -> Self {
+        Self {
+            program,
+            chunk: Chunk::lower(program.get_ast()),
+        }
+    }
+
+    pub fn get_chunk(&self) -> &Chunk {
+        &self.chunk
+    }
+
+    /// Same as `Program::compile_in`: variables of `env` can be used and new ones are stored there
+    pub fn run_in(&self, env: &mut Env) -> CalcRes<'a> {
+        let Chunk {
+            code,
+            spans,
+            consts,
+            idents,
+        } = &self.chunk;
+        let program = self.program;
+        // Every value keeps the span of its expression, so operators can report their operands
+        let mut stack: Vec<(LiteralKind, Span)> = Vec::new();
+        let mut pc = 0;
+
+        while let Some(&instr) = code.get(pc) {
+            let span = spans[pc];
+            pc += 1;
+
+            if let Some(op) = instr.binop() {
+                let (rhs, rhs_span) = pop(&mut stack);
+                let (lhs, lhs_span) = pop(&mut stack);
+                let Lit { kind } =
+                    program.eval_binop(op, vec![lhs, rhs], [lhs_span, rhs_span], span)?;
+                stack.push((kind, span));
+                continue;
+            }
+            if let Some(op) = instr.unop() {
+                let (val, val_span) = pop(&mut stack);
+                let Lit { kind } = program.eval_unop(op, val, val_span, span)?;
+                stack.push((kind, span));
+                continue;
+            }
+
+            match instr {
+                Instr::PushInt(val) => stack.push((LiteralKind::Int { val }, span)),
+                Instr::PushFloat(val) => stack.push((LiteralKind::Float { val }, span)),
+                Instr::PushBool(val) => stack.push((LiteralKind::Bool { val }, span)),
+                Instr::PushConst(i) => stack.push((consts[i].clone(), span)),
+                Instr::Load(i) => {
+                    let Lit { kind } = program.compile_ident(&idents[i], env)?;
+                    stack.push((kind, span));
+                }
+                Instr::Declare(i) => program.check_undefined(&idents[i], env)?,
+                Instr::Store(i) => {
+                    let (kind, _) = stack.last().expect("Value of `let` is on the stack");
+                    env.define(idents[i].name.clone(), Lit { kind: kind.clone() });
+                }
+                Instr::Pop => {
+                    pop(&mut stack);
+                }
+                Instr::Call { ident, args } => {
+                    let ident = &idents[ident];
+                    let builtin = program.lookup_call(ident, args, span)?;
+                    let (vals, spans): (Vec<_>, Vec<_>) = stack.drain(stack.len() - args..).unzip();
+                    let Lit { kind } = program.eval_call(builtin, ident, vals, &spans, span)?;
+                    stack.push((kind, span));
+                }
+                Instr::Jump(target) => pc = target,
+                Instr::JumpIfFalse(target) => {
+                    let (val, val_span) = pop(&mut stack);
+                    if !program.cond_value(val, val_span)? {
+                        pc = target;
+                    }
+                }
+                Instr::JumpIfFalseOrPop(target) | Instr::JumpIfTrueOrPop(target) => {
+                    let (op, decides) = match instr {
+                        Instr::JumpIfFalseOrPop(_) => (BinOpKind::And, false),
+                        _ => (BinOpKind::Or, true),
+                    };
+                    let (val, val_span) = pop(&mut stack);
+
+                    if program.bool_operand(val, op, val_span)? == decides {
+                        stack.push((LiteralKind::Bool { val: decides }, span));
+                        pc = target;
+                    }
+                }
+                Instr::CheckBool(op) => {
+                    let (val, val_span) = pop(&mut stack);
+                    let val = program.bool_operand(val, op, val_span)?;
+                    stack.push((LiteralKind::Bool { val }, span));
+                }
+                _ => unreachable!("Operators are executed above"),
+            }
+        }
+
+        // Parser doesn't allow empty programs, so we always have at least one statement
+        let (kind, _) = pop(&mut stack);
+        Ok(Lit { kind })
+    }
+}
+
+fn pop(stack: &mut Vec<(LiteralKind, Span)>) -> (LiteralKind, Span) {
+    stack
+        .pop()
+        .expect("Bytecode pushes operands before their operators")
+}
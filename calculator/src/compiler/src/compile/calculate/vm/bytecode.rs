@@ -0,0 +1,305 @@
+use crate::{
+    ast_lowering::ast::{
+        Ast, BinOp, BinOpKind, Call, Cond, Expr, ExprKind, Ident, Let, Lit, LiteralKind, Stmt,
+        UnOp, UnOpKind,
+    },
+    span::Span,
+};
+
+use super::super::builtins;
+
+/// Instruction of the stack machine. Operands are popped from the stack
+/// and the result is pushed back, so `1 + 2` is `PushInt(1)`, `PushInt(2)`, `Add`.
+/// Jumps contain the index of the next instruction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Instr {
+    PushInt(i64),
+    PushFloat(f64),
+    PushBool(bool),
+    // Big numbers are too large for an instruction, so they are stored in `Chunk::consts`
+    PushConst(usize),
+    // Variables and functions are stored in `Chunk::idents`
+    Load(usize),
+    // Checks that the variable isn't defined yet, before its value is calculated
+    Declare(usize),
+    // Defines the variable, but leaves its value on the stack as the result of `let`
+    Store(usize),
+    Pop,
+    Call { ident: usize, args: usize },
+    Neg,
+    Pos,
+    BitNot,
+    Not,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    Jump(usize),
+    // Pops the condition of `?:`
+    JumpIfFalse(usize),
+    // Left operand of `&&` and `||` is left on the stack if it decides the result,
+    // otherwise it's popped and the right operand is calculated
+    JumpIfFalseOrPop(usize),
+    JumpIfTrueOrPop(usize),
+    // Checks that the right operand of `&&` or `||` is a boolean
+    CheckBool(BinOpKind),
+}
+
+impl Instr {
+    pub fn binop(self) -> Option<BinOpKind> {
+        let op = match self {
+            Instr::Add => BinOpKind::Add,
+            Instr::Sub => BinOpKind::Sub,
+            Instr::Mul => BinOpKind::Mul,
+            Instr::Div => BinOpKind::Div,
+            Instr::Mod => BinOpKind::Mod,
+            Instr::Pow => BinOpKind::Pow,
+            Instr::BitAnd => BinOpKind::BitAnd,
+            Instr::BitOr => BinOpKind::BitOr,
+            Instr::BitXor => BinOpKind::BitXor,
+            Instr::Shl => BinOpKind::Shl,
+            Instr::Shr => BinOpKind::Shr,
+            Instr::Lt => BinOpKind::Lt,
+            Instr::Le => BinOpKind::Le,
+            Instr::Gt => BinOpKind::Gt,
+            Instr::Ge => BinOpKind::Ge,
+            Instr::Eq => BinOpKind::Eq,
+            Instr::Ne => BinOpKind::Ne,
+            _ => return None,
+        };
+
+        Some(op)
+    }
+
+    pub fn unop(self) -> Option<UnOpKind> {
+        let op = match self {
+            Instr::Neg => UnOpKind::Neg,
+            Instr::Pos => UnOpKind::Pos,
+            Instr::BitNot => UnOpKind::BitNot,
+            Instr::Not => UnOpKind::Not,
+            _ => return None,
+        };
+
+        Some(op)
+    }
+}
+
+impl From<BinOpKind> for Instr {
+    fn from(op: BinOpKind) -> Self {
+        match op {
+            BinOpKind::Add => Instr::Add,
+            BinOpKind::Sub => Instr::Sub,
+            BinOpKind::Mul => Instr::Mul,
+            BinOpKind::Div => Instr::Div,
+            BinOpKind::Mod => Instr::Mod,
+            BinOpKind::Pow => Instr::Pow,
+            BinOpKind::BitAnd => Instr::BitAnd,
+            BinOpKind::BitOr => Instr::BitOr,
+            BinOpKind::BitXor => Instr::BitXor,
+            BinOpKind::Shl => Instr::Shl,
+            BinOpKind::Shr => Instr::Shr,
+            BinOpKind::Lt => Instr::Lt,
+            BinOpKind::Le => Instr::Le,
+            BinOpKind::Gt => Instr::Gt,
+            BinOpKind::Ge => Instr::Ge,
+            BinOpKind::Eq => Instr::Eq,
+            BinOpKind::Ne => Instr::Ne,
+            BinOpKind::And | BinOpKind::Or => {
+                unreachable!("`{op}` is lowered to jumps, because it doesn't always calculate its right operand")
+            }
+        }
+    }
+}
+
+impl From<UnOpKind> for Instr {
+    fn from(op: UnOpKind) -> Self {
+        match op {
+            UnOpKind::Neg => Instr::Neg,
+            UnOpKind::Pos => Instr::Pos,
+            UnOpKind::BitNot => Instr::BitNot,
+            UnOpKind::Not => Instr::Not,
+        }
+    }
+}
+
+/// Bytecode of the whole program. After it's executed, the stack contains only
+/// the result of the last statement.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Chunk {
+    pub code: Vec<Instr>,
+    // Span of the expression that every instruction calculates, for diagnostics
+    pub spans: Vec<Span>,
+    pub consts: Vec<LiteralKind>,
+    pub idents: Vec<Ident>,
+}
+
+// Part of the lowering that is not done yet
+enum Task<'e> {
+    Expr(&'e Expr),
+    Instr(Instr, Span),
+    // Jumps to the label are replaced by jumps to the next instruction
+    Label(usize),
+}
+
+impl Chunk {
+    /// Lowers the program without recursion, so its depth is limited only by the memory
+    pub fn lower(ast: &Ast) -> Self {
+        let mut chunk = Chunk::default();
+        // Index of the instruction that every label points to
+        let mut labels = Vec::new();
+
+        match ast {
+            Ast::Stmts(stmts) => {
+                for (i, stmt) in stmts.iter().enumerate() {
+                    // Only the result of the last statement is left on the stack
+                    if i > 0 {
+                        chunk.push(Instr::Pop, Span::default());
+                    }
+
+                    match stmt {
+                        Stmt::Expr(expr) => chunk.lower_expr(expr, &mut labels),
+                        Stmt::Let(Let { ident, expr }) => {
+                            let ident = chunk.push_ident(ident);
+                            chunk.push(Instr::Declare(ident), expr.span);
+                            chunk.lower_expr(expr, &mut labels);
+                            chunk.push(Instr::Store(ident), expr.span);
+                        }
+                    }
+                }
+            }
+        }
+
+        for instr in &mut chunk.code {
+            match instr {
+                Instr::Jump(target)
+                | Instr::JumpIfFalse(target)
+                | Instr::JumpIfFalseOrPop(target)
+                | Instr::JumpIfTrueOrPop(target) => *target = labels[*target],
+                _ => (),
+            }
+        }
+
+        chunk
+    }
+
+    // Tasks are done in reverse order of how they are pushed
+    fn lower_expr(&mut self, expr: &Expr, labels: &mut Vec<usize>) {
+        let mut tasks = vec![Task::Expr(expr)];
+
+        while let Some(task) = tasks.pop() {
+            let Expr { kind, span } = match task {
+                Task::Expr(expr) => expr,
+                Task::Instr(instr, span) => {
+                    self.push(instr, span);
+                    continue;
+                }
+                Task::Label(label) => {
+                    labels[label] = self.code.len();
+                    continue;
+                }
+            };
+            let span = *span;
+
+            match kind {
+                ExprKind::Lit(Lit { kind }) => {
+                    let instr = match kind {
+                        LiteralKind::Int { val } => Instr::PushInt(*val),
+                        LiteralKind::Float { val } => Instr::PushFloat(*val),
+                        LiteralKind::Bool { val } => Instr::PushBool(*val),
+                        LiteralKind::BigInt { .. } | LiteralKind::BigDecimal { .. } => {
+                            self.consts.push(kind.clone());
+                            Instr::PushConst(self.consts.len() - 1)
+                        }
+                    };
+                    self.push(instr, span);
+                }
+                ExprKind::Ident(ident) => {
+                    let ident = self.push_ident(ident);
+                    self.push(Instr::Load(ident), span);
+                }
+                ExprKind::UnOp(UnOp { expr, op }) => {
+                    tasks.push(Task::Instr(Instr::from(*op), span));
+                    tasks.push(Task::Expr(expr));
+                }
+                ExprKind::BinOp(BinOp { lhs, rhs, op })
+                    if matches!(op, BinOpKind::And | BinOpKind::Or) =>
+                {
+                    let end = new_label(labels);
+                    let jump = match op {
+                        BinOpKind::And => Instr::JumpIfFalseOrPop(end),
+                        _ => Instr::JumpIfTrueOrPop(end),
+                    };
+
+                    tasks.push(Task::Label(end));
+                    tasks.push(Task::Instr(Instr::CheckBool(*op), span));
+                    tasks.push(Task::Expr(rhs));
+                    tasks.push(Task::Instr(jump, span));
+                    tasks.push(Task::Expr(lhs));
+                }
+                ExprKind::BinOp(BinOp { lhs, rhs, op }) => {
+                    tasks.push(Task::Instr(Instr::from(*op), span));
+                    tasks.push(Task::Expr(rhs));
+                    tasks.push(Task::Expr(lhs));
+                }
+                ExprKind::Call(Call { ident, args }) => {
+                    let call = Instr::Call {
+                        ident: self.push_ident(ident),
+                        args: args.len(),
+                    };
+                    tasks.push(Task::Instr(call, span));
+
+                    // Unknown functions and wrong number of arguments are reported
+                    // before the arguments are calculated, so they are not even lowered
+                    let is_valid = builtins::lookup(&ident.name)
+                        .is_some_and(|builtin| builtin.arity.accepts(args.len()));
+                    if is_valid {
+                        tasks.extend(args.iter().rev().map(Task::Expr));
+                    }
+                }
+                ExprKind::Cond(Cond {
+                    cond,
+                    if_true,
+                    if_false,
+                }) => {
+                    let (if_false_label, end) = (new_label(labels), new_label(labels));
+
+                    tasks.push(Task::Label(end));
+                    tasks.push(Task::Expr(if_false));
+                    tasks.push(Task::Label(if_false_label));
+                    tasks.push(Task::Instr(Instr::Jump(end), span));
+                    tasks.push(Task::Expr(if_true));
+                    tasks.push(Task::Instr(Instr::JumpIfFalse(if_false_label), span));
+                    tasks.push(Task::Expr(cond));
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, instr: Instr, span: Span) {
+        self.code.push(instr);
+        self.spans.push(span);
+    }
+
+    fn push_ident(&mut self, ident: &Ident) -> usize {
+        self.idents.push(ident.clone());
+        self.idents.len() - 1
+    }
+}
+
+fn new_label(labels: &mut Vec<usize>) -> usize {
+    labels.push(0);
+    labels.len() - 1
+}
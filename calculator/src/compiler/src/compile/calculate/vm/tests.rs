@@ -0,0 +1,220 @@
+use std::{cell::RefCell, collections::HashSet, fs, path::Path, rc::Rc};
+
+use super::{Chunk, Instr, Vm};
+use crate::{
+    ast_lowering::ast::{BinOpKind, Lit, LiteralKind},
+    compile::{
+        calculate::{CalcRes, Program},
+        Backend, CompileOptions, MixingPolicy, NumMode, SOURCE_FILE_EXTENSION,
+    },
+    errors::{
+        diagnostic::{Diagnostic, DiagnosticCtxt},
+        emitter::Emitter,
+    },
+    parser::Parser,
+};
+
+// Remembers all diagnostics, so we can check that both backends report the same ones
+#[derive(Debug, Default)]
+struct RecordEmitter {
+    diags: Rc<RefCell<Vec<String>>>,
+}
+
+impl Emitter for RecordEmitter {
+    fn emit_diag(&self, diag: &Diagnostic) {
+        self.diags.borrow_mut().push(format!(
+            "error: {} at {:?}",
+            diag.get_msg(),
+            diag.get_span()
+        ));
+    }
+
+    fn emit_warn(&self, diag: &Diagnostic) {
+        self.diags.borrow_mut().push(format!(
+            "warning: {} at {:?}",
+            diag.get_msg(),
+            diag.get_span()
+        ));
+    }
+}
+
+// Result (or error message) of the program and all its diagnostics.
+// Results are compared as strings, because `NaN` is not equal to itself.
+fn run(
+    options: CompileOptions,
+    compile: impl for<'a> FnOnce(&'a DiagnosticCtxt, CompileOptions) -> CalcRes<'a>,
+) -> (Result<String, String>, Vec<String>) {
+    let diags = Rc::new(RefCell::new(Vec::new()));
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(RecordEmitter {
+        diags: diags.clone(),
+    }));
+    let res = compile(&diag_ctxt, options)
+        .map(|lit| format!("{:?}", lit.kind))
+        .map_err(|err| err.get_msg());
+
+    (res, diags.take())
+}
+
+fn assert_backends_agree(
+    options: CompileOptions,
+    compile: impl for<'a> Fn(&'a DiagnosticCtxt, CompileOptions) -> CalcRes<'a>,
+) {
+    let tree = run(
+        CompileOptions {
+            backend: Backend::Tree,
+            ..options
+        },
+        &compile,
+    );
+    let vm = run(
+        CompileOptions {
+            backend: Backend::Vm,
+            ..options
+        },
+        &compile,
+    );
+
+    assert_eq!(tree, vm);
+}
+
+// Programs are not simplified, so the vm calculates all their operations
+fn compile_unsimplified<'a>(
+    src: &str,
+    diag_ctxt: &'a DiagnosticCtxt,
+    options: CompileOptions,
+) -> CalcRes<'a> {
+    let root =
+        Parser::from_source(src, diag_ctxt).lowering_parse(HashSet::new(), options.num_mode)?;
+
+    Program::new(root, "".to_string(), diag_ctxt, options).compile()
+}
+
+#[test]
+fn test_backends_agree_on_examples() {
+    let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples");
+    let mut paths: Vec<_> = fs::read_dir(examples)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == SOURCE_FILE_EXTENSION)
+        })
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty());
+
+    for path in paths {
+        let src = fs::read_to_string(&path).unwrap();
+
+        for num_mode in [NumMode::Native, NumMode::Big] {
+            let options = CompileOptions {
+                num_mode,
+                ..Default::default()
+            };
+
+            assert_backends_agree(options, |diag_ctxt, options| {
+                Program::from_source_file(path.to_string_lossy().to_string(), diag_ctxt, options)?
+                    .compile()
+            });
+            assert_backends_agree(options, |diag_ctxt, options| {
+                compile_unsimplified(&src, diag_ctxt, options)
+            });
+        }
+    }
+}
+
+#[test]
+fn test_backends_agree_on_errors() {
+    let programs = [
+        "let x = 2; x * (3 - 3) + max(x, 1.5) / 0",
+        "let x = 1; let x = 1 / 0",
+        "let x = 9223372036854775807; 1 + x",
+        "let b = 1 < 2; b && 2 > 3 || !b ? 1 : foo(1 / 0)",
+        "let b = 1; b || true",
+        "let b = false; b || 2",
+        "let x = 2; x ? 1 : 2",
+        "let x = 1.5; gcd(4, x)",
+        "let x = 1.5; sqrt(x, 2) + ~x",
+        "let x = 0.0; ln(x) + 1.0 / x - 1.0",
+        "let x = 3; x << 64 | 1",
+        "let b = true; -b",
+    ];
+
+    for src in programs {
+        for mixing in [MixingPolicy::Promote, MixingPolicy::Strict] {
+            let options = CompileOptions {
+                mixing,
+                ..Default::default()
+            };
+
+            assert_backends_agree(options, |diag_ctxt, options| {
+                compile_unsimplified(src, diag_ctxt, options)
+            });
+        }
+    }
+}
+
+#[test]
+fn test_lower_to_bytecode() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(RecordEmitter::default()));
+    let program = Program::from_source(
+        "let x = 2; x * 3 + 1 > 6 && x < 5 ? x : -x",
+        "".to_string(),
+        &diag_ctxt,
+    )
+    .unwrap();
+    let Chunk { code, idents, .. } = Vm::new(&program).get_chunk().clone();
+
+    assert_eq!(
+        code,
+        vec![
+            Instr::Declare(0),
+            Instr::PushInt(2),
+            Instr::Store(0),
+            Instr::Pop,
+            Instr::Load(1),
+            Instr::PushInt(3),
+            Instr::Mul,
+            Instr::PushInt(1),
+            Instr::Add,
+            Instr::PushInt(6),
+            Instr::Gt,
+            Instr::JumpIfFalseOrPop(16),
+            Instr::Load(2),
+            Instr::PushInt(5),
+            Instr::Lt,
+            Instr::CheckBool(BinOpKind::And),
+            Instr::JumpIfFalse(19),
+            Instr::Load(3),
+            Instr::Jump(21),
+            Instr::Load(4),
+            Instr::Neg,
+        ]
+    );
+    assert!(idents.iter().all(|ident| ident.name == "x"));
+}
+
+#[test]
+fn test_deeply_nested_expression() {
+    // Much deeper than the stack of the test thread would allow without growing it
+    let depth = 100_000;
+    let src = format!("let x = 1; {}x{}", "(x + ".repeat(depth), ")".repeat(depth));
+
+    for backend in [Backend::Tree, Backend::Vm] {
+        let diag_ctxt = DiagnosticCtxt::new(Box::new(RecordEmitter::default()));
+        let options = CompileOptions {
+            backend,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            compile_unsimplified(&src, &diag_ctxt, options).unwrap(),
+            Lit {
+                kind: LiteralKind::Int {
+                    val: depth as i64 + 1
+                }
+            },
+            "{backend:?}"
+        );
+    }
+}
@@ -0,0 +1,94 @@
+mod asm;
+mod c;
+mod errors;
+mod llvm;
+#[cfg(test)]
+mod tests;
+mod typed;
+
+use super::{Compile, Emit, Env, Program};
+use crate::errors::diagnostic::Diagnostic;
+use typed::Typer;
+
+/// Language of the generated code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    C,
+    // x86-64 assembly for the System V ABI in the GNU syntax
+    Asm,
+    // Textual LLVM IR, it's generated without LLVM libraries
+    Llvm,
+}
+
+impl Target {
+    /// Target of the code that is emitted instead of the result, if any
+    pub fn from_emit(emit: Emit) -> Option<Self> {
+        match emit {
+            Emit::C => Some(Target::C),
+            Emit::Asm => Some(Target::Asm),
+            Emit::Llvm => Some(Target::Llvm),
+            Emit::Result | Emit::Simplified => None,
+        }
+    }
+
+    /// Extension of the file with the generated code
+    pub fn extension(self) -> &'static str {
+        match self {
+            Target::C => "c",
+            Target::Asm => "s",
+            Target::Llvm => "ll",
+        }
+    }
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Target::C => write!(f, "C"),
+            Target::Asm => write!(f, "assembly"),
+            Target::Llvm => write!(f, "LLVM IR"),
+        }
+    }
+}
+
+/// Backend that translates the program to a standalone program in another language,
+/// which prints the same result. Only 64-bit numbers can be translated.
+pub struct Codegen<'p, 'a> {
+    program: &'p Program<'a>,
+    target: Target,
+}
+
+impl<'p, 'a> Compile for Codegen<'p, 'a> {
+    type Ret = Result<String, Diagnostic<'a>>;
+
+    fn compile(&self) -> Self::Ret {
+        self.compile_in(&mut Env::new())
+    }
+}
+
+impl<'p, 'a> Codegen<'p, 'a> {
+    pub fn new(program: &'p Program<'a>, target: Target) -> Self {
+        Self { program, target }
+    }
+
+    /// Variables of `env` are translated to their values and new variables are stored there.
+    pub fn compile_in(&self, env: &mut Env) -> Result<String, Diagnostic<'a>> {
+        // The program is calculated first, so all its diagnostics are reported as usual
+        // and the generated code doesn't need to check for errors, like division by zero
+        self.program.compile_in(env)?;
+
+        let program = Typer::new(
+            self.program.get_diag_ctxt(),
+            env,
+            self.program.get_options(),
+            self.target,
+        )
+        .type_program(self.program.get_ast())?;
+
+        Ok(match self.target {
+            Target::C => c::generate(&program),
+            Target::Asm => asm::generate(&program),
+            Target::Llvm => llvm::generate(&program),
+        })
+    }
+}
@@ -0,0 +1,492 @@
+use std::fmt::Write as _;
+
+use super::typed::{Func, TExpr, TExprKind, TProgram, Type};
+use crate::{
+    ast_lowering::ast::{BinOpKind, UnOpKind},
+    helpers::stack::ensure_sufficient_stack,
+};
+
+// Functions that are called by the generated code, they keep the System V calling convention.
+// `calc_print_float` prints floats the same way as Rust: the shortest representation
+// that is parsed back to the same value, without an exponent.
+const HELPERS: &str = r#"
+calc_pow_int:
+    mov $1, %eax
+.Lpow_loop:
+    test %rsi, %rsi
+    jle .Lpow_done
+    test $1, %sil
+    jz .Lpow_square
+    imul %rdi, %rax
+.Lpow_square:
+    imul %rdi, %rdi
+    sar $1, %rsi
+    jmp .Lpow_loop
+.Lpow_done:
+    ret
+
+calc_gcd:
+    mov %rdi, %rax
+    neg %rax
+    cmovs %rdi, %rax
+    mov %rsi, %rcx
+    neg %rcx
+    cmovs %rsi, %rcx
+.Lgcd_loop:
+    test %rcx, %rcx
+    jz .Lgcd_done
+    xor %edx, %edx
+    div %rcx
+    mov %rcx, %rax
+    mov %rdx, %rcx
+    jmp .Lgcd_loop
+.Lgcd_done:
+    ret
+
+calc_print_float:
+    push %rbp
+    mov %rsp, %rbp
+    sub $80, %rsp
+    movsd %xmm0, -8(%rbp)
+    ucomisd %xmm0, %xmm0
+    jp .Lprint_nan
+    movq %xmm0, %rcx
+    btr $63, %rcx
+    movabs $0x7ff0000000000000, %rax
+    cmp %rax, %rcx
+    je .Lprint_inf
+    movl $1, -16(%rbp)
+.Lprint_try:
+    lea -64(%rbp), %rdi
+    mov $32, %esi
+    lea .Lfmt_exp(%rip), %rdx
+    mov -16(%rbp), %ecx
+    dec %ecx
+    movsd -8(%rbp), %xmm0
+    mov $1, %eax
+    call snprintf@PLT
+    cmpl $17, -16(%rbp)
+    je .Lprint_found
+    lea -64(%rbp), %rdi
+    xor %esi, %esi
+    call strtod@PLT
+    ucomisd -8(%rbp), %xmm0
+    je .Lprint_found
+    incl -16(%rbp)
+    jmp .Lprint_try
+.Lprint_found:
+    lea -64(%rbp), %rdi
+    mov $101, %esi
+    call strchr@PLT
+    lea 1(%rax), %rdi
+    call atoi@PLT
+    mov %eax, -24(%rbp)
+    mov -16(%rbp), %ecx
+    dec %ecx
+    cmp %ecx, %eax
+    jge .Lprint_digits
+    sub %eax, %ecx
+    mov %ecx, %esi
+    lea .Lfmt_fixed(%rip), %rdi
+    movsd -8(%rbp), %xmm0
+    mov $1, %eax
+    call printf@PLT
+    jmp .Lprint_done
+.Lprint_digits:
+    lea -64(%rbp), %rax
+    mov %rax, -72(%rbp)
+.Lprint_digit:
+    mov -72(%rbp), %rax
+    movzbl (%rax), %edi
+    cmp $101, %edi
+    je .Lprint_zeros
+    incq -72(%rbp)
+    cmp $46, %edi
+    je .Lprint_digit
+    call putchar@PLT
+    jmp .Lprint_digit
+.Lprint_zeros:
+    mov -16(%rbp), %ecx
+    dec %ecx
+    mov -24(%rbp), %eax
+    sub %ecx, %eax
+    mov %eax, -80(%rbp)
+.Lprint_zero:
+    cmpl $0, -80(%rbp)
+    jle .Lprint_newline
+    mov $48, %edi
+    call putchar@PLT
+    decl -80(%rbp)
+    jmp .Lprint_zero
+.Lprint_newline:
+    mov $10, %edi
+    call putchar@PLT
+    jmp .Lprint_done
+.Lprint_nan:
+    lea .Lstr_nan(%rip), %rdi
+    call puts@PLT
+    jmp .Lprint_done
+.Lprint_inf:
+    lea .Lstr_inf(%rip), %rdi
+    lea .Lstr_neg_inf(%rip), %rax
+    movq %xmm0, %rcx
+    test %rcx, %rcx
+    cmovs %rax, %rdi
+    call puts@PLT
+.Lprint_done:
+    leave
+    ret
+
+    .section .rodata
+.Lfmt_int:
+    .string "%ld\n"
+.Lfmt_exp:
+    .string "%.*e"
+.Lfmt_fixed:
+    .string "%.*f\n"
+.Lstr_true:
+    .string "true"
+.Lstr_false:
+    .string "false"
+.Lstr_nan:
+    .string "NaN"
+.Lstr_inf:
+    .string "inf"
+.Lstr_neg_inf:
+    .string "-inf"
+
+    .section .note.GNU-stack,"",@progbits
+"#;
+
+const SIGN_MASK: u64 = 1 << 63;
+
+/// Translates the program to x86-64 assembly in the GNU syntax. Integers and booleans
+/// are calculated in `%rax` and floats in `%xmm0`, operands are kept on the stack,
+/// variables are stored in the frame of `main`.
+pub fn generate(program: &TProgram) -> String {
+    let mut gen = AsmGen::default();
+    // Frame must be aligned to 16 bytes for calls
+    let frame = (program.vars.len() * 8).next_multiple_of(16);
+
+    gen.code.push_str("    .text\n    .globl main\nmain:\n");
+    gen.instr("push %rbp");
+    gen.instr("mov %rsp, %rbp");
+    if frame > 0 {
+        gen.instr(&format!("sub ${frame}, %rsp"));
+    }
+
+    for (i, var) in program.vars.iter().enumerate() {
+        gen.expr(var);
+        let slot = var_slot(i);
+        match var.ty {
+            Type::Float => gen.instr(&format!("movsd %xmm0, {slot}")),
+            Type::Int | Type::Bool => gen.instr(&format!("mov %rax, {slot}")),
+        }
+    }
+
+    gen.expr(&program.result);
+    match program.result.ty {
+        Type::Int => {
+            gen.instr("mov %rax, %rsi");
+            gen.instr("lea .Lfmt_int(%rip), %rdi");
+            gen.instr("xor %eax, %eax");
+            gen.instr("call printf@PLT");
+        }
+        Type::Float => gen.instr("call calc_print_float"),
+        Type::Bool => {
+            gen.instr("lea .Lstr_true(%rip), %rdi");
+            gen.instr("lea .Lstr_false(%rip), %rcx");
+            gen.instr("test %rax, %rax");
+            gen.instr("cmovz %rcx, %rdi");
+            gen.instr("call puts@PLT");
+        }
+    }
+    gen.instr("xor %eax, %eax");
+    gen.instr("leave");
+    gen.instr("ret");
+
+    gen.code.push_str(HELPERS);
+    gen.code
+}
+
+fn var_slot(var: usize) -> String {
+    format!("-{}(%rbp)", (var + 1) * 8)
+}
+
+#[derive(Default)]
+struct AsmGen {
+    code: String,
+    labels: usize,
+    // Number of 8-byte values that are pushed on the stack above the frame
+    depth: usize,
+}
+
+impl AsmGen {
+    fn instr(&mut self, instr: &str) {
+        let _ = writeln!(self.code, "    {instr}");
+    }
+
+    fn label(&mut self) -> String {
+        self.labels += 1;
+        format!(".L{}", self.labels)
+    }
+
+    fn place(&mut self, label: &str) {
+        let _ = writeln!(self.code, "{label}:");
+    }
+
+    // Stack must be aligned to 16 bytes before a call
+    fn call(&mut self, func: &str) {
+        let pad = self.depth % 2 == 1;
+        if pad {
+            self.instr("sub $8, %rsp");
+        }
+        self.instr(&format!("call {func}"));
+        if pad {
+            self.instr("add $8, %rsp");
+        }
+    }
+
+    fn push(&mut self, ty: Type) {
+        match ty {
+            Type::Float => {
+                self.instr("sub $8, %rsp");
+                self.instr("movsd %xmm0, (%rsp)");
+            }
+            Type::Int | Type::Bool => self.instr("push %rax"),
+        }
+        self.depth += 1;
+    }
+
+    // Moves the current value to the second register, `%rcx` or `%xmm1`,
+    // and pops the previous one to the first register
+    fn pop_under(&mut self, ty: Type) {
+        match ty {
+            Type::Float => {
+                self.instr("movapd %xmm0, %xmm1");
+                self.instr("movsd (%rsp), %xmm0");
+                self.instr("add $8, %rsp");
+            }
+            Type::Int | Type::Bool => {
+                self.instr("mov %rax, %rcx");
+                self.instr("pop %rax");
+            }
+        }
+        self.depth -= 1;
+    }
+
+    fn load_float_bits(&mut self, bits: u64, reg: &str) {
+        self.instr(&format!("movabs ${bits:#x}, %rax"));
+        self.instr(&format!("movq %rax, {reg}"));
+    }
+
+    fn expr(&mut self, TExpr { kind, ty }: &TExpr) {
+        ensure_sufficient_stack(|| match kind {
+            TExprKind::Int(val) => self.instr(&format!("movabs ${val}, %rax")),
+            TExprKind::Float(val) => self.load_float_bits(val.to_bits(), "%xmm0"),
+            TExprKind::Bool(val) => self.instr(&format!("mov ${}, %eax", u8::from(*val))),
+            TExprKind::Var(i) => match ty {
+                Type::Float => self.instr(&format!("movsd {}, %xmm0", var_slot(*i))),
+                Type::Int | Type::Bool => self.instr(&format!("mov {}, %rax", var_slot(*i))),
+            },
+            TExprKind::IntToFloat(expr) => {
+                self.expr(expr);
+                self.instr("cvtsi2sd %rax, %xmm0");
+            }
+            TExprKind::UnOp(op, expr) => {
+                self.expr(expr);
+                match (op, ty) {
+                    (UnOpKind::Neg, Type::Float) => {
+                        self.load_float_bits(SIGN_MASK, "%xmm1");
+                        self.instr("xorpd %xmm1, %xmm0");
+                    }
+                    (UnOpKind::Neg, _) => self.instr("neg %rax"),
+                    (UnOpKind::BitNot, _) => self.instr("not %rax"),
+                    (UnOpKind::Not, _) => self.instr("xor $1, %rax"),
+                    (UnOpKind::Pos, _) => {}
+                }
+            }
+            TExprKind::BinOp(op @ (BinOpKind::And | BinOpKind::Or), lhs, rhs) => {
+                // Bools are `0` or `1`, so the value of `lhs` is the result if `rhs` is skipped
+                let end = self.label();
+                self.expr(lhs);
+                self.instr("test %rax, %rax");
+                let jump = if *op == BinOpKind::And { "jz" } else { "jnz" };
+                self.instr(&format!("{jump} {end}"));
+                self.expr(rhs);
+                self.place(&end);
+            }
+            TExprKind::BinOp(op, lhs, rhs) => {
+                self.expr(lhs);
+                self.push(lhs.ty);
+                self.expr(rhs);
+                self.pop_under(rhs.ty);
+
+                match lhs.ty {
+                    Type::Float => self.float_binop(*op),
+                    Type::Int | Type::Bool => self.int_binop(*op),
+                }
+            }
+            TExprKind::Call(func, args) => self.call_func(*func, args, *ty),
+            TExprKind::Cond(cond, if_true, if_false) => {
+                let (if_false_label, end) = (self.label(), self.label());
+                self.expr(cond);
+                self.instr("test %rax, %rax");
+                self.instr(&format!("jz {if_false_label}"));
+                self.expr(if_true);
+                self.instr(&format!("jmp {end}"));
+                self.place(&if_false_label);
+                self.expr(if_false);
+                self.place(&end);
+            }
+        })
+    }
+
+    // `%rax` is `lhs` and `%rcx` is `rhs`
+    fn int_binop(&mut self, op: BinOpKind) {
+        let instrs: &[&str] = match op {
+            BinOpKind::Add => &["add %rcx, %rax"],
+            BinOpKind::Sub => &["sub %rcx, %rax"],
+            BinOpKind::Mul => &["imul %rcx, %rax"],
+            BinOpKind::Div => &["cqto", "idiv %rcx"],
+            BinOpKind::Mod => &["cqto", "idiv %rcx", "mov %rdx, %rax"],
+            BinOpKind::BitAnd => &["and %rcx, %rax"],
+            BinOpKind::BitOr => &["or %rcx, %rax"],
+            BinOpKind::BitXor => &["xor %rcx, %rax"],
+            BinOpKind::Shl => &["shl %cl, %rax"],
+            BinOpKind::Shr => &["sar %cl, %rax"],
+            BinOpKind::Pow => {
+                self.instr("mov %rax, %rdi");
+                self.instr("mov %rcx, %rsi");
+                self.call("calc_pow_int");
+                return;
+            }
+            _ => {
+                let set = match op {
+                    BinOpKind::Lt => "setl",
+                    BinOpKind::Le => "setle",
+                    BinOpKind::Gt => "setg",
+                    BinOpKind::Ge => "setge",
+                    BinOpKind::Eq => "sete",
+                    _ => "setne",
+                };
+                self.instr("cmp %rcx, %rax");
+                self.instr(&format!("{set} %al"));
+                self.instr("movzbl %al, %eax");
+                return;
+            }
+        };
+
+        for instr in instrs {
+            self.instr(instr);
+        }
+    }
+
+    // `%xmm0` is `lhs` and `%xmm1` is `rhs`. Comparisons with `NaN` set the parity flag
+    // and are false, except for `!=`.
+    fn float_binop(&mut self, op: BinOpKind) {
+        let instrs: &[&str] = match op {
+            BinOpKind::Add => &["addsd %xmm1, %xmm0"],
+            BinOpKind::Sub => &["subsd %xmm1, %xmm0"],
+            BinOpKind::Mul => &["mulsd %xmm1, %xmm0"],
+            BinOpKind::Div => &["divsd %xmm1, %xmm0"],
+            BinOpKind::Mod => {
+                self.call("fmod@PLT");
+                return;
+            }
+            BinOpKind::Pow => {
+                self.call("pow@PLT");
+                return;
+            }
+            BinOpKind::Gt => &["ucomisd %xmm1, %xmm0", "seta %al"],
+            BinOpKind::Ge => &["ucomisd %xmm1, %xmm0", "setae %al"],
+            BinOpKind::Lt => &["ucomisd %xmm0, %xmm1", "seta %al"],
+            BinOpKind::Le => &["ucomisd %xmm0, %xmm1", "setae %al"],
+            BinOpKind::Eq => &[
+                "ucomisd %xmm1, %xmm0",
+                "sete %al",
+                "setnp %cl",
+                "and %cl, %al",
+            ],
+            BinOpKind::Ne => &[
+                "ucomisd %xmm1, %xmm0",
+                "setne %al",
+                "setp %cl",
+                "or %cl, %al",
+            ],
+            _ => unreachable!("Operator can't be applied to floats"),
+        };
+
+        for instr in instrs {
+            self.instr(instr);
+        }
+        if op.is_comparison() {
+            self.instr("movzbl %al, %eax");
+        }
+    }
+
+    fn call_func(&mut self, func: Func, args: &[TExpr], ty: Type) {
+        let (first, rest) = args.split_first().expect("Arity is checked");
+        self.expr(first);
+
+        match func {
+            Func::Libm(name) => {
+                // Functions of the math library take one or two floats
+                for arg in rest {
+                    self.push(Type::Float);
+                    self.expr(arg);
+                    self.pop_under(Type::Float);
+                }
+                self.call(&format!("{name}@PLT"));
+            }
+            Func::Abs if ty == Type::Float => {
+                self.load_float_bits(!SIGN_MASK, "%xmm1");
+                self.instr("andpd %xmm1, %xmm0");
+            }
+            Func::Abs => {
+                self.instr("mov %rax, %rcx");
+                self.instr("neg %rcx");
+                self.instr("cmovns %rcx, %rax");
+            }
+            Func::Gcd => {
+                self.push(Type::Int);
+                self.expr(&rest[0]);
+                self.pop_under(Type::Int);
+                self.instr("mov %rax, %rdi");
+                self.instr("mov %rcx, %rsi");
+                self.call("calc_gcd");
+            }
+            // The next argument replaces the current minimum if it's less, so `NaN` is never chosen
+            // after the first argument, as in the calculation
+            Func::Min | Func::Max => {
+                for arg in rest {
+                    self.push(ty);
+                    self.expr(arg);
+                    self.pop_under(ty);
+
+                    match (ty, func) {
+                        (Type::Float, _) => {
+                            let skip = self.label();
+                            if func == Func::Min {
+                                self.instr("ucomisd %xmm1, %xmm0");
+                            } else {
+                                self.instr("ucomisd %xmm0, %xmm1");
+                            }
+                            self.instr(&format!("jbe {skip}"));
+                            self.instr("movapd %xmm1, %xmm0");
+                            self.place(&skip);
+                        }
+                        (_, Func::Min) => {
+                            self.instr("cmp %rax, %rcx");
+                            self.instr("cmovl %rcx, %rax");
+                        }
+                        _ => {
+                            self.instr("cmp %rax, %rcx");
+                            self.instr("cmovg %rcx, %rax");
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
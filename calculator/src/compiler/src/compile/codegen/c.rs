@@ -0,0 +1,282 @@
+use std::fmt::Write as _;
+
+use super::typed::{Func, TExpr, TExprKind, TProgram, Type};
+use crate::{
+    ast_lowering::ast::{BinOpKind, UnOpKind},
+    helpers::stack::ensure_sufficient_stack,
+};
+
+// Integer operations that are undefined in C for some operands are done on unsigned integers.
+// The program was calculated before, so they never overflow, but intermediate values can.
+// Floats are printed the same way as by Rust: the shortest representation that is parsed back
+// to the same value, without an exponent.
+const PRELUDE: &str = r#"#include <inttypes.h>
+#include <math.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+static inline int64_t calc_pow_int(int64_t base, int64_t exp) {
+    uint64_t res = 1, pow = (uint64_t)base;
+    for (; exp > 0; exp >>= 1) {
+        if (exp & 1) {
+            res *= pow;
+        }
+        pow *= pow;
+    }
+    return (int64_t)res;
+}
+
+static inline int64_t calc_shl(int64_t val, int64_t shift) {
+    return (int64_t)((uint64_t)val << shift);
+}
+
+static inline int64_t calc_abs_int(int64_t val) {
+    return val < 0 ? -val : val;
+}
+
+static inline int64_t calc_gcd(int64_t lhs, int64_t rhs) {
+    uint64_t a = lhs < 0 ? -(uint64_t)lhs : (uint64_t)lhs;
+    uint64_t b = rhs < 0 ? -(uint64_t)rhs : (uint64_t)rhs;
+    while (b != 0) {
+        uint64_t rem = a % b;
+        a = b;
+        b = rem;
+    }
+    return (int64_t)a;
+}
+
+static inline int64_t calc_min_int(int64_t min, int64_t val) {
+    return val < min ? val : min;
+}
+
+static inline int64_t calc_max_int(int64_t max, int64_t val) {
+    return val > max ? val : max;
+}
+
+static inline double calc_min_float(double min, double val) {
+    return val < min ? val : min;
+}
+
+static inline double calc_max_float(double max, double val) {
+    return val > max ? val : max;
+}
+
+static void calc_print_float(double val) {
+    if (isnan(val)) {
+        puts("NaN");
+        return;
+    }
+    if (isinf(val)) {
+        puts(val > 0 ? "inf" : "-inf");
+        return;
+    }
+
+    char buf[32];
+    int digits = 1;
+    for (; digits < 17; digits++) {
+        snprintf(buf, sizeof(buf), "%.*e", digits - 1, val);
+        if (strtod(buf, NULL) == val) {
+            break;
+        }
+    }
+    snprintf(buf, sizeof(buf), "%.*e", digits - 1, val);
+
+    int exp = atoi(strchr(buf, 'e') + 1);
+    if (exp < digits - 1) {
+        printf("%.*f\n", digits - 1 - exp, val);
+        return;
+    }
+    for (char *c = buf; *c != 'e'; c++) {
+        if (*c != '.') {
+            putchar(*c);
+        }
+    }
+    for (int i = 0; i < exp - (digits - 1); i++) {
+        putchar('0');
+    }
+    putchar('\n');
+}
+"#;
+
+/// Translates the program to C. Expressions are fully parenthesized.
+pub fn generate(program: &TProgram) -> String {
+    let mut code = PRELUDE.to_string();
+
+    code.push_str("\nint main(void) {\n");
+    for (i, var) in program.vars.iter().enumerate() {
+        let _ = write!(code, "    const {} var_{i} = ", c_type(var.ty));
+        write_expr(&mut code, var);
+        code.push_str(";\n");
+    }
+
+    let print = match program.result.ty {
+        Type::Int => "printf(\"%\" PRId64 \"\\n\", ",
+        Type::Float => "calc_print_float(",
+        Type::Bool => "puts((",
+    };
+    code.push_str("    ");
+    code.push_str(print);
+    write_expr(&mut code, &program.result);
+    if program.result.ty == Type::Bool {
+        code.push_str(") ? \"true\" : \"false\"");
+    }
+    code.push_str(");\n    return 0;\n}\n");
+
+    code
+}
+
+fn c_type(ty: Type) -> &'static str {
+    match ty {
+        Type::Int => "int64_t",
+        Type::Float => "double",
+        Type::Bool => "bool",
+    }
+}
+
+fn write_expr(code: &mut String, TExpr { kind, ty }: &TExpr) {
+    ensure_sufficient_stack(|| match kind {
+        TExprKind::Int(val) => write_int(code, *val),
+        TExprKind::Float(val) => write_float(code, *val),
+        TExprKind::Bool(val) => code.push_str(if *val { "true" } else { "false" }),
+        TExprKind::Var(i) => {
+            let _ = write!(code, "var_{i}");
+        }
+        TExprKind::IntToFloat(expr) => {
+            code.push_str("((double)");
+            write_expr(code, expr);
+            code.push(')');
+        }
+        TExprKind::UnOp(op, expr) => {
+            let op = match op {
+                UnOpKind::Neg => "-",
+                UnOpKind::Pos => "+",
+                UnOpKind::BitNot => "~",
+                UnOpKind::Not => "!",
+            };
+            code.push('(');
+            code.push_str(op);
+            write_expr(code, expr);
+            code.push(')');
+        }
+        TExprKind::BinOp(op, lhs, rhs) => write_binop(code, *op, lhs, rhs),
+        TExprKind::Call(func, args) => write_call(code, *func, args, *ty),
+        TExprKind::Cond(cond, if_true, if_false) => {
+            code.push('(');
+            write_expr(code, cond);
+            code.push_str(" ? ");
+            write_expr(code, if_true);
+            code.push_str(" : ");
+            write_expr(code, if_false);
+            code.push(')');
+        }
+    })
+}
+
+fn write_int(code: &mut String, val: i64) {
+    // `-9223372036854775808` is a negation of a literal that doesn't fit in `int64_t`
+    if val == i64::MIN {
+        code.push_str("INT64_MIN");
+    } else {
+        let _ = write!(code, "(INT64_C({val}))");
+    }
+}
+
+fn write_float(code: &mut String, val: f64) {
+    if val.is_nan() {
+        code.push_str("NAN");
+    } else if val.is_infinite() {
+        code.push_str(if val > 0.0 { "INFINITY" } else { "(-INFINITY)" });
+    } else {
+        // `Debug` always prints a float literal, like `1.0` or `1e21`
+        let _ = write!(code, "({val:?})");
+    }
+}
+
+fn write_binop(code: &mut String, op: BinOpKind, lhs: &TExpr, rhs: &TExpr) {
+    let is_float = lhs.ty == Type::Float;
+    let func = match op {
+        BinOpKind::Pow if is_float => Some("pow"),
+        BinOpKind::Pow => Some("calc_pow_int"),
+        BinOpKind::Mod if is_float => Some("fmod"),
+        BinOpKind::Shl => Some("calc_shl"),
+        _ => None,
+    };
+    if let Some(func) = func {
+        write_call_args(code, func, &[lhs, rhs]);
+        return;
+    }
+
+    let op = match op {
+        BinOpKind::Add => "+",
+        BinOpKind::Sub => "-",
+        BinOpKind::Mul => "*",
+        BinOpKind::Div => "/",
+        BinOpKind::Mod => "%",
+        BinOpKind::BitAnd => "&",
+        BinOpKind::BitOr => "|",
+        BinOpKind::BitXor => "^",
+        // Right shift of a negative number is arithmetic in all supported compilers
+        BinOpKind::Shr => ">>",
+        BinOpKind::Lt => "<",
+        BinOpKind::Le => "<=",
+        BinOpKind::Gt => ">",
+        BinOpKind::Ge => ">=",
+        BinOpKind::Eq => "==",
+        BinOpKind::Ne => "!=",
+        BinOpKind::And => "&&",
+        BinOpKind::Or => "||",
+        BinOpKind::Pow | BinOpKind::Shl => unreachable!("Operator is translated to a call"),
+    };
+    code.push('(');
+    write_expr(code, lhs);
+    let _ = write!(code, " {op} ");
+    write_expr(code, rhs);
+    code.push(')');
+}
+
+fn write_call(code: &mut String, func: Func, args: &[TExpr], ty: Type) {
+    let suffix = match ty {
+        Type::Float => "float",
+        _ => "int",
+    };
+    let name = match func {
+        Func::Libm(name) => name,
+        Func::Abs if ty == Type::Float => "fabs",
+        Func::Abs => "calc_abs_int",
+        Func::Gcd => "calc_gcd",
+        Func::Min | Func::Max => {
+            let name = match func {
+                Func::Min => format!("calc_min_{suffix}"),
+                _ => format!("calc_max_{suffix}"),
+            };
+            // Arguments are compared from left to right, so `min(a, b, c)` is
+            // `min(min(a, b), c)`
+            let (first, rest) = args.split_first().expect("Arity is checked");
+            code.push_str(&format!("{name}(").repeat(rest.len()));
+            write_expr(code, first);
+            for arg in rest {
+                code.push_str(", ");
+                write_expr(code, arg);
+                code.push(')');
+            }
+            return;
+        }
+    };
+
+    write_call_args(code, name, &args.iter().collect::<Vec<_>>());
+}
+
+fn write_call_args(code: &mut String, func: &str, args: &[&TExpr]) {
+    code.push_str(func);
+    code.push('(');
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            code.push_str(", ");
+        }
+        write_expr(code, arg);
+    }
+    code.push(')');
+}
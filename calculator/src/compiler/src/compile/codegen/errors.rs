@@ -0,0 +1,52 @@
+use crate::errors::diagnostic::{Diagnostic, DiagnosticCtxt, DiagnosticMsg, IntoDiagnostic};
+use crate::span::Span;
+use calculator_macros::IntoDiagnostic;
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("big numbers can't be compiled to {}")]
+pub struct BigNumbersCodegen {
+    target: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl BigNumbersCodegen {
+    pub fn new(target: String, span: Span) -> Self {
+        Self { target, span }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("branches of `?:` have different types `{}` and `{}`")]
+pub struct MismatchedBranchTypes {
+    if_true: String,
+    if_false: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl MismatchedBranchTypes {
+    pub fn new(if_true: String, if_false: String, span: Span) -> Self {
+        Self {
+            if_true,
+            if_false,
+            span,
+        }
+    }
+}
+
+// Code that is never calculated isn't checked by the calculation of the program
+#[derive(IntoDiagnostic)]
+#[diagnostic("`{}` can't be applied to `{}`")]
+pub struct InvalidOperandCodegen {
+    op: String,
+    found: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl InvalidOperandCodegen {
+    pub fn new(op: String, found: String, span: Span) -> Self {
+        Self { op, found, span }
+    }
+}
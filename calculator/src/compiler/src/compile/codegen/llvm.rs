@@ -0,0 +1,399 @@
+use std::{collections::BTreeMap, fmt::Write as _};
+
+use super::typed::{Func, TExpr, TExprKind, TProgram, Type};
+use crate::{
+    ast_lowering::ast::{BinOpKind, UnOpKind},
+    helpers::stack::ensure_sufficient_stack,
+};
+
+// Functions that are called by the generated code. `calc_print_float` prints floats the same way
+// as Rust: the shortest representation that is parsed back to the same value, without an exponent.
+// Pointers are typed, so the IR can be read by LLVM 14.
+const HELPERS: &str = r#"
+@.fmt.int = private unnamed_addr constant [5 x i8] c"%ld\0A\00"
+@.fmt.exp = private unnamed_addr constant [5 x i8] c"%.*e\00"
+@.fmt.fixed = private unnamed_addr constant [6 x i8] c"%.*f\0A\00"
+@.str.true = private unnamed_addr constant [5 x i8] c"true\00"
+@.str.false = private unnamed_addr constant [6 x i8] c"false\00"
+@.str.nan = private unnamed_addr constant [4 x i8] c"NaN\00"
+@.str.inf = private unnamed_addr constant [4 x i8] c"inf\00"
+@.str.neg_inf = private unnamed_addr constant [5 x i8] c"-inf\00"
+
+declare i32 @printf(i8*, ...)
+declare i32 @snprintf(i8*, i64, i8*, ...)
+declare i32 @puts(i8*)
+declare i32 @putchar(i32)
+declare double @strtod(i8*, i8**)
+declare i8* @strchr(i8*, i32)
+declare i32 @atoi(i8*)
+declare double @pow(double, double)
+declare i64 @llvm.abs.i64(i64, i1)
+declare double @llvm.fabs.f64(double)
+
+define internal i64 @calc_pow_int(i64 %base, i64 %exp) {
+entry:
+  br label %loop
+loop:
+  %res = phi i64 [ 1, %entry ], [ %res.next, %body ]
+  %pow = phi i64 [ %base, %entry ], [ %pow.next, %body ]
+  %e = phi i64 [ %exp, %entry ], [ %e.next, %body ]
+  %done = icmp sle i64 %e, 0
+  br i1 %done, label %exit, label %body
+body:
+  %bit = and i64 %e, 1
+  %odd = icmp ne i64 %bit, 0
+  %mul = mul i64 %res, %pow
+  %res.next = select i1 %odd, i64 %mul, i64 %res
+  %pow.next = mul i64 %pow, %pow
+  %e.next = ashr i64 %e, 1
+  br label %loop
+exit:
+  ret i64 %res
+}
+
+define internal i64 @calc_gcd(i64 %lhs, i64 %rhs) {
+entry:
+  %a.abs = call i64 @llvm.abs.i64(i64 %lhs, i1 false)
+  %b.abs = call i64 @llvm.abs.i64(i64 %rhs, i1 false)
+  br label %loop
+loop:
+  %a = phi i64 [ %a.abs, %entry ], [ %b, %body ]
+  %b = phi i64 [ %b.abs, %entry ], [ %rem, %body ]
+  %done = icmp eq i64 %b, 0
+  br i1 %done, label %exit, label %body
+body:
+  %rem = urem i64 %a, %b
+  br label %loop
+exit:
+  ret i64 %a
+}
+
+define internal void @calc_print_float(double %val) {
+entry:
+  %buf = alloca [32 x i8]
+  %p = getelementptr inbounds [32 x i8], [32 x i8]* %buf, i64 0, i64 0
+  %is_nan = fcmp uno double %val, %val
+  br i1 %is_nan, label %nan, label %not_nan
+nan:
+  call i32 @puts(i8* getelementptr inbounds ([4 x i8], [4 x i8]* @.str.nan, i64 0, i64 0))
+  ret void
+not_nan:
+  %abs = call double @llvm.fabs.f64(double %val)
+  %is_inf = fcmp oeq double %abs, 0x7FF0000000000000
+  br i1 %is_inf, label %inf, label %try
+inf:
+  %neg = fcmp olt double %val, 0.0
+  %inf.str = select i1 %neg, i8* getelementptr inbounds ([5 x i8], [5 x i8]* @.str.neg_inf, i64 0, i64 0), i8* getelementptr inbounds ([4 x i8], [4 x i8]* @.str.inf, i64 0, i64 0)
+  call i32 @puts(i8* %inf.str)
+  ret void
+try:
+  %digits = phi i32 [ 1, %not_nan ], [ %digits.next, %retry ]
+  %prec = sub i32 %digits, 1
+  call i32 (i8*, i64, i8*, ...) @snprintf(i8* %p, i64 32, i8* getelementptr inbounds ([5 x i8], [5 x i8]* @.fmt.exp, i64 0, i64 0), i32 %prec, double %val)
+  %last = icmp eq i32 %digits, 17
+  br i1 %last, label %found, label %check
+check:
+  %parsed = call double @strtod(i8* %p, i8** null)
+  %same = fcmp oeq double %parsed, %val
+  br i1 %same, label %found, label %retry
+retry:
+  %digits.next = add i32 %digits, 1
+  br label %try
+found:
+  %e = call i8* @strchr(i8* %p, i32 101)
+  %exp.str = getelementptr inbounds i8, i8* %e, i64 1
+  %exp = call i32 @atoi(i8* %exp.str)
+  %is_fixed = icmp slt i32 %exp, %prec
+  br i1 %is_fixed, label %fixed, label %digit
+fixed:
+  %frac = sub i32 %prec, %exp
+  call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([6 x i8], [6 x i8]* @.fmt.fixed, i64 0, i64 0), i32 %frac, double %val)
+  ret void
+digit:
+  %i = phi i64 [ 0, %found ], [ %i.next, %digit.next ]
+  %c.ptr = getelementptr inbounds i8, i8* %p, i64 %i
+  %c = load i8, i8* %c.ptr
+  %i.next = add i64 %i, 1
+  %is_e = icmp eq i8 %c, 101
+  br i1 %is_e, label %zeros, label %digit.check
+digit.check:
+  %is_dot = icmp eq i8 %c, 46
+  br i1 %is_dot, label %digit.next, label %digit.print
+digit.print:
+  %c.int = sext i8 %c to i32
+  call i32 @putchar(i32 %c.int)
+  br label %digit.next
+digit.next:
+  br label %digit
+zeros:
+  %zeros.count = sub i32 %exp, %prec
+  br label %zero
+zero:
+  %n = phi i32 [ %zeros.count, %zeros ], [ %n.next, %zero.print ]
+  %more = icmp sgt i32 %n, 0
+  br i1 %more, label %zero.print, label %newline
+zero.print:
+  call i32 @putchar(i32 48)
+  %n.next = sub i32 %n, 1
+  br label %zero
+newline:
+  call i32 @putchar(i32 10)
+  ret void
+}
+"#;
+
+/// Translates the program to textual LLVM IR with a `main` function that prints the result.
+/// Values are SSA registers, `?:`, `&&` and `||` are translated to branches and `phi`.
+pub fn generate(program: &TProgram) -> String {
+    let mut gen = IrGen {
+        block: "entry".to_string(),
+        ..Default::default()
+    };
+
+    for var in &program.vars {
+        let val = gen.expr(var);
+        gen.vars.push(val);
+    }
+
+    let res = gen.expr(&program.result);
+    match program.result.ty {
+        Type::Int => gen.instr(&format!(
+            "call i32 (i8*, ...) @printf(i8* {}, i64 {res})",
+            str_ptr("fmt.int", 5)
+        )),
+        Type::Float => gen.instr(&format!("call void @calc_print_float(double {res})")),
+        Type::Bool => {
+            let s = gen.assign(&format!(
+                "select i1 {res}, i8* {}, i8* {}",
+                str_ptr("str.true", 5),
+                str_ptr("str.false", 6)
+            ));
+            gen.instr(&format!("call i32 @puts(i8* {s})"));
+        }
+    }
+    gen.instr("ret i32 0");
+
+    let mut ir = String::from("; Generated by the calculator\n");
+    // Functions of the math library that are used by the program
+    for (name, arity) in &gen.libm {
+        let params = vec!["double"; *arity].join(", ");
+        let _ = writeln!(ir, "declare double @{name}({params})");
+    }
+    ir.push_str(HELPERS);
+    ir.push_str("\ndefine i32 @main() {\nentry:\n");
+    ir.push_str(&gen.code);
+    ir.push_str("}\n");
+
+    ir
+}
+
+fn str_ptr(name: &str, len: usize) -> String {
+    format!("getelementptr inbounds ([{len} x i8], [{len} x i8]* @.{name}, i64 0, i64 0)")
+}
+
+fn ir_type(ty: Type) -> &'static str {
+    match ty {
+        Type::Int => "i64",
+        Type::Float => "double",
+        Type::Bool => "i1",
+    }
+}
+
+#[derive(Default)]
+struct IrGen {
+    code: String,
+    regs: usize,
+    labels: usize,
+    // Label of the block where the next instruction is placed, it's needed for `phi`
+    block: String,
+    // Registers or constants with the values of the variables
+    vars: Vec<String>,
+    // Functions of the math library with their number of arguments
+    libm: BTreeMap<&'static str, usize>,
+}
+
+impl IrGen {
+    fn instr(&mut self, instr: &str) {
+        let _ = writeln!(self.code, "  {instr}");
+    }
+
+    // Adds an instruction that returns a value and returns the register with it
+    fn assign(&mut self, instr: &str) -> String {
+        self.regs += 1;
+        let reg = format!("%t{}", self.regs);
+        let _ = writeln!(self.code, "  {reg} = {instr}");
+        reg
+    }
+
+    fn label(&mut self) -> String {
+        self.labels += 1;
+        format!("L{}", self.labels)
+    }
+
+    fn place(&mut self, label: &str) {
+        let _ = writeln!(self.code, "{label}:");
+        self.block = label.to_string();
+    }
+
+    // Returns the register or the constant with the value of the expression
+    fn expr(&mut self, TExpr { kind, ty }: &TExpr) -> String {
+        ensure_sufficient_stack(|| match kind {
+            TExprKind::Int(val) => val.to_string(),
+            // Hexadecimal representation is exact
+            TExprKind::Float(val) => format!("0x{:016X}", val.to_bits()),
+            TExprKind::Bool(val) => val.to_string(),
+            TExprKind::Var(i) => self.vars[*i].clone(),
+            TExprKind::IntToFloat(expr) => {
+                let val = self.expr(expr);
+                self.assign(&format!("sitofp i64 {val} to double"))
+            }
+            TExprKind::UnOp(op, expr) => {
+                let val = self.expr(expr);
+                match (op, ty) {
+                    (UnOpKind::Neg, Type::Float) => self.assign(&format!("fneg double {val}")),
+                    (UnOpKind::Neg, _) => self.assign(&format!("sub i64 0, {val}")),
+                    (UnOpKind::BitNot, _) => self.assign(&format!("xor i64 {val}, -1")),
+                    (UnOpKind::Not, _) => self.assign(&format!("xor i1 {val}, true")),
+                    (UnOpKind::Pos, _) => val,
+                }
+            }
+            TExprKind::BinOp(op @ (BinOpKind::And | BinOpKind::Or), lhs, rhs) => {
+                let (rhs_label, end) = (self.label(), self.label());
+                let lhs = self.expr(lhs);
+                let lhs_block = self.block.clone();
+                // The value of `lhs` is the result if `rhs` is skipped
+                let (skip, short) = match op {
+                    BinOpKind::And => ("false", format!("label %{rhs_label}, label %{end}")),
+                    _ => ("true", format!("label %{end}, label %{rhs_label}")),
+                };
+                self.instr(&format!("br i1 {lhs}, {short}"));
+
+                self.place(&rhs_label);
+                let rhs = self.expr(rhs);
+                let rhs_block = self.block.clone();
+                self.instr(&format!("br label %{end}"));
+
+                self.place(&end);
+                self.assign(&format!(
+                    "phi i1 [ {skip}, %{lhs_block} ], [ {rhs}, %{rhs_block} ]"
+                ))
+            }
+            TExprKind::BinOp(op, lhs, rhs) => {
+                let ty = lhs.ty;
+                let (lhs, rhs) = (self.expr(lhs), self.expr(rhs));
+                self.binop(*op, ty, &lhs, &rhs)
+            }
+            TExprKind::Call(func, args) => self.call(*func, args, *ty),
+            TExprKind::Cond(cond, if_true, if_false) => {
+                let (true_label, false_label, end) = (self.label(), self.label(), self.label());
+                let cond = self.expr(cond);
+                self.instr(&format!(
+                    "br i1 {cond}, label %{true_label}, label %{false_label}"
+                ));
+
+                self.place(&true_label);
+                let if_true = self.expr(if_true);
+                let true_block = self.block.clone();
+                self.instr(&format!("br label %{end}"));
+
+                self.place(&false_label);
+                let if_false = self.expr(if_false);
+                let false_block = self.block.clone();
+                self.instr(&format!("br label %{end}"));
+
+                self.place(&end);
+                self.assign(&format!(
+                    "phi {} [ {if_true}, %{true_block} ], [ {if_false}, %{false_block} ]",
+                    ir_type(*ty)
+                ))
+            }
+        })
+    }
+
+    // Both operands have the type `ty`
+    fn binop(&mut self, op: BinOpKind, ty: Type, lhs: &str, rhs: &str) -> String {
+        let ir_ty = ir_type(ty);
+        let instr = match (op, ty) {
+            (BinOpKind::Pow, Type::Float) => {
+                return self.assign(&format!("call double @pow(double {lhs}, double {rhs})"))
+            }
+            (BinOpKind::Pow, _) => {
+                return self.assign(&format!("call i64 @calc_pow_int(i64 {lhs}, i64 {rhs})"))
+            }
+            (BinOpKind::Add, Type::Float) => "fadd",
+            (BinOpKind::Sub, Type::Float) => "fsub",
+            (BinOpKind::Mul, Type::Float) => "fmul",
+            (BinOpKind::Div, Type::Float) => "fdiv",
+            // `frem` is the same as `fmod` from C
+            (BinOpKind::Mod, Type::Float) => "frem",
+            // Comparisons with `NaN` are false, except for `!=`
+            (BinOpKind::Lt, Type::Float) => "fcmp olt",
+            (BinOpKind::Le, Type::Float) => "fcmp ole",
+            (BinOpKind::Gt, Type::Float) => "fcmp ogt",
+            (BinOpKind::Ge, Type::Float) => "fcmp oge",
+            (BinOpKind::Eq, Type::Float) => "fcmp oeq",
+            (BinOpKind::Ne, Type::Float) => "fcmp une",
+            (BinOpKind::Add, _) => "add",
+            (BinOpKind::Sub, _) => "sub",
+            (BinOpKind::Mul, _) => "mul",
+            (BinOpKind::Div, _) => "sdiv",
+            (BinOpKind::Mod, _) => "srem",
+            (BinOpKind::BitAnd, _) => "and",
+            (BinOpKind::BitOr, _) => "or",
+            (BinOpKind::BitXor, _) => "xor",
+            (BinOpKind::Shl, _) => "shl",
+            (BinOpKind::Shr, _) => "ashr",
+            (BinOpKind::Lt, _) => "icmp slt",
+            (BinOpKind::Le, _) => "icmp sle",
+            (BinOpKind::Gt, _) => "icmp sgt",
+            (BinOpKind::Ge, _) => "icmp sge",
+            (BinOpKind::Eq, _) => "icmp eq",
+            (BinOpKind::Ne, _) => "icmp ne",
+            (BinOpKind::And | BinOpKind::Or, _) => {
+                unreachable!("Operator is translated to branches")
+            }
+        };
+
+        self.assign(&format!("{instr} {ir_ty} {lhs}, {rhs}"))
+    }
+
+    fn call(&mut self, func: Func, args: &[TExpr], ty: Type) -> String {
+        let args: Vec<_> = args.iter().map(|arg| self.expr(arg)).collect();
+
+        match func {
+            Func::Libm(name) => {
+                self.libm.insert(name, args.len());
+                let args: Vec<_> = args.iter().map(|arg| format!("double {arg}")).collect();
+                self.assign(&format!("call double @{name}({})", args.join(", ")))
+            }
+            Func::Abs if ty == Type::Float => {
+                self.assign(&format!("call double @llvm.fabs.f64(double {})", args[0]))
+            }
+            Func::Abs => self.assign(&format!(
+                "call i64 @llvm.abs.i64(i64 {}, i1 false)",
+                args[0]
+            )),
+            Func::Gcd => self.assign(&format!(
+                "call i64 @calc_gcd(i64 {}, i64 {})",
+                args[0], args[1]
+            )),
+            // The next argument replaces the current minimum if it's less, so `NaN` is never chosen
+            // after the first argument, as in the calculation
+            Func::Min | Func::Max => {
+                let (first, rest) = args.split_first().expect("Arity is checked");
+                let cmp = match (ty, func) {
+                    (Type::Float, Func::Min) => "fcmp olt",
+                    (Type::Float, _) => "fcmp ogt",
+                    (_, Func::Min) => "icmp slt",
+                    _ => "icmp sgt",
+                };
+                let ir_ty = ir_type(ty);
+
+                rest.iter().fold(first.clone(), |acc, arg| {
+                    let less = self.assign(&format!("{cmp} {ir_ty} {arg}, {acc}"));
+                    self.assign(&format!("select i1 {less}, {ir_ty} {arg}, {ir_ty} {acc}"))
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        OnceLock,
+    },
+};
+
+use super::{
+    typed::{TExpr, TExprKind, Type, Typer},
+    Codegen, Target,
+};
+use crate::{
+    compile::{Compile, CompileOptions, Env, IntDivision, NumMode, Program, SOURCE_FILE_EXTENSION},
+    errors::{
+        diagnostic::{Diagnostic, DiagnosticCtxt},
+        emitter::SilentEmitter,
+    },
+};
+
+static BUILDS: AtomicUsize = AtomicUsize::new(0);
+
+// Program that builds or runs the generated code
+fn tool(target: Target) -> &'static str {
+    match target {
+        Target::C | Target::Asm => "cc",
+        Target::Llvm => "lli",
+    }
+}
+
+static AVAILABLE_TARGETS: OnceLock<Vec<Target>> = OnceLock::new();
+
+// Targets whose code can be run on this system. The others are skipped with a message,
+// which is shown with `cargo test -- --nocapture` or if the test fails
+fn available_targets() -> Vec<Target> {
+    AVAILABLE_TARGETS
+        .get_or_init(|| {
+            [Target::C, Target::Asm, Target::Llvm]
+                .into_iter()
+                .filter(|target| {
+                    let available = Command::new(tool(*target))
+                        .arg("--version")
+                        .output()
+                        .is_ok();
+                    if !available {
+                        println!(
+                            "skipped running {target} code: `{}` is not found",
+                            tool(*target)
+                        );
+                    }
+                    available
+                })
+                .collect()
+        })
+        .clone()
+}
+
+// Compiles the generated code with the system compiler (or interprets LLVM IR)
+// and returns the output of the program
+fn run_generated(code: &str, target: Target) -> String {
+    let dir = std::env::temp_dir().join(format!(
+        "calc-codegen-{}-{}",
+        std::process::id(),
+        BUILDS.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    let src = dir.join(format!("program.{}", target.extension()));
+    let bin = dir.join("program");
+    fs::write(&src, code).unwrap();
+
+    let run = match target {
+        Target::C | Target::Asm => {
+            let build = Command::new(tool(target))
+                .arg(&src)
+                .arg("-o")
+                .arg(&bin)
+                .arg("-lm")
+                .output()
+                .unwrap();
+            assert!(
+                build.status.success(),
+                "{}\n{code}",
+                String::from_utf8_lossy(&build.stderr)
+            );
+            Command::new(&bin).output().unwrap()
+        }
+        Target::Llvm => Command::new(tool(target)).arg(&src).output().unwrap(),
+    };
+    assert!(
+        run.status.success(),
+        "{}\n{code}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+    fs::remove_dir_all(&dir).unwrap();
+
+    String::from_utf8(run.stdout).unwrap()
+}
+
+fn generate<'a>(
+    src: &str,
+    diag_ctxt: &'a DiagnosticCtxt,
+    options: CompileOptions,
+    target: Target,
+) -> Result<String, Diagnostic<'a>> {
+    let program = Program::from_source_in(src, "".to_string(), diag_ctxt, &Env::new(), options)?;
+    Codegen::new(&program, target).compile()
+}
+
+// All targets print the same result as the calculation of the program
+fn assert_same_result(src: &str, options: CompileOptions) {
+    // Warnings about mixed types are expected, errors are checked by the results
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(SilentEmitter));
+    let expected = Program::from_source_in(src, "".to_string(), &diag_ctxt, &Env::new(), options)
+        .and_then(|program| program.compile())
+        .map(|res| format!("{}\n", res.kind))
+        .unwrap_or_else(|err| panic!("{src}: {}", err.get_msg()));
+
+    for target in available_targets() {
+        let code = generate(src, &diag_ctxt, options, target).unwrap();
+        assert_eq!(run_generated(&code, target), expected, "{target}: {src}");
+    }
+}
+
+fn codegen_err(src: &str, options: CompileOptions) -> String {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(SilentEmitter));
+    generate(src, &diag_ctxt, options, Target::C)
+        .unwrap_err()
+        .get_msg()
+}
+
+#[test]
+fn test_codegen_examples() {
+    let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples");
+    let mut paths: Vec<PathBuf> = fs::read_dir(examples)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == SOURCE_FILE_EXTENSION)
+        })
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty());
+
+    for path in paths {
+        let src = fs::read_to_string(&path).unwrap();
+        assert_same_result(&src, CompileOptions::default());
+    }
+}
+
+#[test]
+fn test_codegen_programs() {
+    let programs = [
+        "let x = 6; let y = x * 7 - 1; y / 4 + y % 4",
+        "let x = -7; x / 2 * 10 + x % 3",
+        "let x = 3; x ** 4 + 2 ** 62 - 1",
+        "let x = 1; x << 62",
+        "let x = -256; (x >> 4) ^ ~x & 255 | 1",
+        "let x = 5; x > 3 && x <= 5 || x == 0",
+        "let x = 2; let b = x != 2; !b",
+        "let x = 4; x < 3 ? 1 : x < 5 ? 2 : 1 / 0",
+        "let x = 10; abs(-x) + gcd(x, -4) + min(x, 3, 7) + max(1, x, 2)",
+        "let x = 2.5; min(x, 1.5, 3.0) + max(x, -1.0) + abs(-x)",
+        "let x = 2; sqrt(x) + ln(x) + atan2(1, x) + hypot(3, 4) + round(-x * 1.25)",
+        "let x = 7.5; x % 2 + x ** 0.5 - -x",
+        "let x = 1.5; x < 2 && x >= 1.5 && x != 1.0 && !(x == 2.0) && x > 0",
+        "let x = 0.0; let y = x / x; y == y || y != y && y < 1.0",
+        "let x = 1; let b = true; b",
+    ];
+
+    for src in programs {
+        assert_same_result(src, CompileOptions::default());
+    }
+
+    let options = CompileOptions {
+        int_div: IntDivision::Float,
+        ..Default::default()
+    };
+    assert_same_result("let x = 7; x / 2 + 1 / 3", options);
+}
+
+#[test]
+fn test_codegen_prints_floats() {
+    let floats = [
+        "1.0",
+        "1e21",
+        "0.1 + 0.2",
+        "-0.0",
+        "1.0 / 0.0",
+        "-1.0 / 0.0",
+        "0.0 / 0.0",
+        "1.2345678901234567e30",
+        "1e-7",
+        "-123.456",
+        "5e-324",
+    ];
+
+    for float in floats {
+        assert_same_result(
+            &format!("let x = 1.0; x * ({float})"),
+            CompileOptions::default(),
+        );
+    }
+}
+
+#[test]
+fn test_llvm_ir() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(SilentEmitter));
+    let ir = generate(
+        "let x = 2.0; let b = x > 1; b ? sqrt(x) : atan2(x, 1)",
+        &diag_ctxt,
+        CompileOptions::default(),
+        Target::Llvm,
+    )
+    .unwrap();
+
+    assert!(ir.contains("declare double @atan2(double, double)\ndeclare double @sqrt(double)\n"));
+    assert!(ir.contains("declare i32 @printf(i8*, ...)"));
+    // Variables with constant values are used directly
+    assert!(ir.ends_with(
+        "define i32 @main() {
+entry:
+  %t1 = sitofp i64 1 to double
+  %t2 = fcmp ogt double 0x4000000000000000, %t1
+  br i1 %t2, label %L1, label %L2
+L1:
+  %t3 = call double @sqrt(double 0x4000000000000000)
+  br label %L3
+L2:
+  %t4 = sitofp i64 1 to double
+  %t5 = call double @atan2(double 0x4000000000000000, double %t4)
+  br label %L3
+L3:
+  %t6 = phi double [ %t3, %L1 ], [ %t5, %L2 ]
+  call void @calc_print_float(double %t6)
+  ret i32 0
+}
+"
+    ));
+}
+
+#[test]
+fn test_codegen_types() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(SilentEmitter));
+    let program = Program::from_source("let x = 2; x + 0.5", "".to_string(), &diag_ctxt).unwrap();
+    let mut env = Env::new();
+    program.compile_in(&mut env).unwrap();
+
+    let typed = Typer::new(&diag_ctxt, &env, CompileOptions::default(), Target::C)
+        .type_program(program.get_ast())
+        .unwrap();
+    let var = TExpr {
+        kind: TExprKind::Var(0),
+        ty: Type::Int,
+    };
+
+    assert_eq!(typed.vars.len(), 1);
+    assert_eq!(typed.result.ty, Type::Float);
+    assert!(matches!(
+        &typed.result.kind,
+        TExprKind::BinOp(_, lhs, _) if lhs.kind == TExprKind::IntToFloat(Box::new(var))
+    ));
+}
+
+#[test]
+fn test_codegen_big_numbers() {
+    let options = CompileOptions {
+        num_mode: NumMode::Big,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        codegen_err("let x = 2; x ** 100", options),
+        "big numbers can't be compiled to C"
+    );
+}
+
+#[test]
+fn test_codegen_mismatched_branches() {
+    assert_eq!(
+        codegen_err("let x = 1; x > 0 ? 1 : 2.0", CompileOptions::default()),
+        "branches of `?:` have different types `Int` and `Float`"
+    );
+    assert_eq!(
+        codegen_err("let x = 1; x > 0 ? x : 1 + true", CompileOptions::default()),
+        "`+` can't be applied to `Int, Bool`"
+    );
+}
+
+#[test]
+fn test_codegen_keeps_calculation_errors() {
+    assert_eq!(
+        codegen_err("let x = 0; 1 / x", CompileOptions::default()),
+        "attempt to compute `1 / 0`, which is a division by zero"
+    );
+}
+
+#[test]
+fn test_codegen_deeply_nested() {
+    let depth = 100_000;
+    let src = format!("let x = 1; {}x{}", "(x + ".repeat(depth), ")".repeat(depth));
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(SilentEmitter));
+
+    for target in [Target::C, Target::Asm, Target::Llvm] {
+        assert!(generate(&src, &diag_ctxt, CompileOptions::default(), target).is_ok());
+    }
+}
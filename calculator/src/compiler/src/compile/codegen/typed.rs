@@ -0,0 +1,399 @@
+use std::collections::HashMap;
+
+use super::{
+    errors::{BigNumbersCodegen, InvalidOperandCodegen, MismatchedBranchTypes},
+    Target,
+};
+use crate::{
+    ast_lowering::ast::{
+        Ast, BinOp, BinOpKind, Call, Cond, Expr, ExprKind, Ident, Let, Lit, LiteralKind, Stmt,
+        UnOp, UnOpKind,
+    },
+    compile::{
+        calculate::builtins::{self, BuiltinKind, IntFn, NumFn},
+        CompileOptions, Env, IntDivision,
+    },
+    errors::diagnostic::{Diagnostic, DiagnosticCtxt},
+    helpers::stack::ensure_sufficient_stack,
+    span::Span,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Type {
+    Int,
+    Float,
+    Bool,
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Int => write!(f, "Int"),
+            Type::Float => write!(f, "Float"),
+            Type::Bool => write!(f, "Bool"),
+        }
+    }
+}
+
+/// Program in which every expression has a type that is known before it's calculated,
+/// and integers are explicitly converted to floats, so it can be translated
+/// to a statically typed language.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TProgram {
+    // Values of the variables in the order of their definition
+    pub vars: Vec<TExpr>,
+    pub result: TExpr,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TExpr {
+    pub kind: TExprKind,
+    pub ty: Type,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TExprKind {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    // Index of the variable in `TProgram::vars`
+    Var(usize),
+    IntToFloat(Box<TExpr>),
+    // `+x` is just `x`, so there are only `-`, `~` and `!`
+    UnOp(UnOpKind, Box<TExpr>),
+    // Both operands have the same type
+    BinOp(BinOpKind, Box<TExpr>, Box<TExpr>),
+    Call(Func, Vec<TExpr>),
+    Cond(Box<TExpr>, Box<TExpr>, Box<TExpr>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Func {
+    // Function from the C math library, like `sqrt`, with float arguments
+    Libm(&'static str),
+    Abs,
+    Min,
+    Max,
+    Gcd,
+}
+
+// The default drop of `Box` is recursive, so deeply nested expressions are dropped
+// without recursion, as in `Ast::drop_iteratively()`
+impl Drop for TProgram {
+    fn drop(&mut self) {
+        let mut exprs = std::mem::take(&mut self.vars);
+        exprs.push(std::mem::replace(
+            &mut self.result,
+            TExpr::new(TExprKind::Bool(false), Type::Bool),
+        ));
+
+        // Children are moved out before their parent is dropped
+        while let Some(expr) = exprs.pop() {
+            match expr.kind {
+                TExprKind::IntToFloat(expr) | TExprKind::UnOp(_, expr) => exprs.push(*expr),
+                TExprKind::BinOp(_, lhs, rhs) => exprs.extend([*lhs, *rhs]),
+                TExprKind::Call(_, args) => exprs.extend(args),
+                TExprKind::Cond(cond, if_true, if_false) => {
+                    exprs.extend([*cond, *if_true, *if_false])
+                }
+                TExprKind::Int(_)
+                | TExprKind::Float(_)
+                | TExprKind::Bool(_)
+                | TExprKind::Var(_) => {}
+            }
+        }
+    }
+}
+
+impl TExpr {
+    fn new(kind: TExprKind, ty: Type) -> Self {
+        Self { kind, ty }
+    }
+
+    fn into_float(self) -> Self {
+        match self.ty {
+            Type::Int => TExpr::new(TExprKind::IntToFloat(Box::new(self)), Type::Float),
+            _ => self,
+        }
+    }
+
+    fn is_number(&self) -> bool {
+        self.ty != Type::Bool
+    }
+}
+
+/// Finds types of all expressions of the program. The program must be calculated
+/// before, so we know that its types are correct; only the code that was never calculated
+/// can still have errors.
+pub struct Typer<'a, 'e> {
+    diag_ctxt: &'a DiagnosticCtxt,
+    // Variables that are defined before the program, they are translated to their values
+    env: &'e Env,
+    options: CompileOptions,
+    target: Target,
+    vars: HashMap<String, (usize, Type)>,
+}
+
+type TypeRes<'a> = Result<TExpr, Diagnostic<'a>>;
+
+impl<'a, 'e> Typer<'a, 'e> {
+    pub fn new(
+        diag_ctxt: &'a DiagnosticCtxt,
+        env: &'e Env,
+        options: CompileOptions,
+        target: Target,
+    ) -> Self {
+        Self {
+            diag_ctxt,
+            env,
+            options,
+            target,
+            vars: HashMap::new(),
+        }
+    }
+
+    pub fn type_program(mut self, ast: &Ast) -> Result<TProgram, Diagnostic<'a>> {
+        let Ast::Stmts(stmts) = ast;
+        let mut vars = Vec::new();
+
+        // Results of the statements before the last one are not used and can't have side effects
+        let (last, stmts) = stmts
+            .split_last()
+            .expect("Program does not contain any statement");
+        for stmt in stmts {
+            if let Stmt::Let(let_stmt) = stmt {
+                self.type_let(let_stmt, &mut vars)?;
+            }
+        }
+        let result = match last {
+            Stmt::Let(let_stmt) => self.type_let(let_stmt, &mut vars)?,
+            Stmt::Expr(expr) => self.type_expr(expr)?,
+        };
+
+        Ok(TProgram { vars, result })
+    }
+
+    // Returns the variable, which is the result of `let`
+    fn type_let(&mut self, Let { ident, expr }: &Let, vars: &mut Vec<TExpr>) -> TypeRes<'a> {
+        let expr = self.type_expr(expr)?;
+        let var = TExpr::new(TExprKind::Var(vars.len()), expr.ty);
+
+        self.vars.insert(ident.name.clone(), (vars.len(), expr.ty));
+        vars.push(expr);
+
+        Ok(var)
+    }
+
+    fn type_expr(&mut self, Expr { kind, span }: &Expr) -> TypeRes<'a> {
+        ensure_sufficient_stack(|| match kind {
+            ExprKind::Lit(Lit { kind }) => self.type_lit(kind, *span),
+            ExprKind::Ident(ident) => self.type_ident(ident),
+            ExprKind::UnOp(unop) => self.type_unop(unop, *span),
+            ExprKind::BinOp(binop) => self.type_binop(binop, *span),
+            ExprKind::Call(call) => self.type_call(call, *span),
+            ExprKind::Cond(cond) => self.type_cond(cond, *span),
+        })
+    }
+
+    fn type_lit(&self, kind: &LiteralKind, span: Span) -> TypeRes<'a> {
+        let expr = match kind {
+            LiteralKind::Int { val } => TExpr::new(TExprKind::Int(*val), Type::Int),
+            LiteralKind::Float { val } => TExpr::new(TExprKind::Float(*val), Type::Float),
+            LiteralKind::Bool { val } => TExpr::new(TExprKind::Bool(*val), Type::Bool),
+            LiteralKind::BigInt { .. } | LiteralKind::BigDecimal { .. } => {
+                let err = self
+                    .diag_ctxt
+                    .handle()
+                    .struct_err(BigNumbersCodegen::new(self.target.to_string(), span))
+                    .with_help("use `--numbers=native` for 64-bit numbers");
+                err.emit();
+
+                return Err(err);
+            }
+        };
+
+        Ok(expr)
+    }
+
+    fn type_ident(&self, Ident { name, span }: &Ident) -> TypeRes<'a> {
+        if let Some((var, ty)) = self.vars.get(name) {
+            return Ok(TExpr::new(TExprKind::Var(*var), *ty));
+        }
+
+        // Names are checked during lowering, so other variables are always in `env`
+        let Lit { kind } = self
+            .env
+            .get(name)
+            .expect("Variable is defined outside of the program");
+        self.type_lit(&kind, *span)
+    }
+
+    fn type_unop(&mut self, UnOp { expr, op }: &UnOp, span: Span) -> TypeRes<'a> {
+        let expr = self.type_expr(expr)?;
+        let ty = expr.ty;
+
+        match (op, ty) {
+            (UnOpKind::Pos, Type::Int | Type::Float) => Ok(expr),
+            (UnOpKind::Neg, Type::Int | Type::Float)
+            | (UnOpKind::BitNot, Type::Int)
+            | (UnOpKind::Not, Type::Bool) => {
+                Ok(TExpr::new(TExprKind::UnOp(*op, Box::new(expr)), ty))
+            }
+            _ => Err(self.invalid_operand(op.to_string(), ty.to_string(), span)),
+        }
+    }
+
+    fn type_binop(&mut self, BinOp { lhs, rhs, op }: &BinOp, span: Span) -> TypeRes<'a> {
+        let (lhs, rhs) = (self.type_expr(lhs)?, self.type_expr(rhs)?);
+        let types = (lhs.ty, rhs.ty);
+        let invalid =
+            || self.invalid_operand(op.to_string(), format!("{}, {}", types.0, types.1), span);
+
+        let (lhs, rhs, ty) = match op {
+            BinOpKind::And | BinOpKind::Or if types == (Type::Bool, Type::Bool) => {
+                (lhs, rhs, Type::Bool)
+            }
+            BinOpKind::Eq | BinOpKind::Ne if types == (Type::Bool, Type::Bool) => {
+                (lhs, rhs, Type::Bool)
+            }
+            _ if op.is_comparison() && lhs.is_number() && rhs.is_number() => {
+                let (lhs, rhs) = unify_pair(lhs, rhs);
+                (lhs, rhs, Type::Bool)
+            }
+            _ if op.is_bitwise() && types == (Type::Int, Type::Int) => (lhs, rhs, Type::Int),
+            // Integers can be divided into a float
+            BinOpKind::Div
+                if types == (Type::Int, Type::Int)
+                    && self.options.int_div == IntDivision::Float =>
+            {
+                (lhs.into_float(), rhs.into_float(), Type::Float)
+            }
+            BinOpKind::Add
+            | BinOpKind::Sub
+            | BinOpKind::Mul
+            | BinOpKind::Div
+            | BinOpKind::Mod
+            | BinOpKind::Pow
+                if lhs.is_number() && rhs.is_number() =>
+            {
+                let (lhs, rhs) = unify_pair(lhs, rhs);
+                let ty = lhs.ty;
+                (lhs, rhs, ty)
+            }
+            _ => return Err(invalid()),
+        };
+
+        Ok(TExpr::new(
+            TExprKind::BinOp(*op, Box::new(lhs), Box::new(rhs)),
+            ty,
+        ))
+    }
+
+    fn type_call(&mut self, Call { ident, args }: &Call, span: Span) -> TypeRes<'a> {
+        let args = args
+            .iter()
+            .map(|arg| self.type_expr(arg))
+            .collect::<Result<Vec<_>, _>>()?;
+        let builtin = builtins::lookup(&ident.name)
+            .filter(|builtin| builtin.arity.accepts(args.len()))
+            .filter(|_| args.iter().all(TExpr::is_number));
+        let invalid = |args: &[TExpr]| {
+            let types: Vec<_> = args.iter().map(|arg| arg.ty.to_string()).collect();
+            self.invalid_operand(ident.name.clone(), types.join(", "), span)
+        };
+        let Some(builtin) = builtin else {
+            return Err(invalid(&args));
+        };
+
+        let (func, args) = match builtin.kind {
+            BuiltinKind::Float(_) => {
+                let name = match builtin.name {
+                    "ln" => "log",
+                    name => name,
+                };
+                let args = args.into_iter().map(TExpr::into_float).collect();
+                (Func::Libm(name), args)
+            }
+            BuiltinKind::Num(f) => {
+                let func = match f {
+                    NumFn::Abs => Func::Abs,
+                    NumFn::Min => Func::Min,
+                    NumFn::Max => Func::Max,
+                };
+                (func, unify(args))
+            }
+            BuiltinKind::Int(IntFn::Gcd) if args.iter().all(|arg| arg.ty == Type::Int) => {
+                (Func::Gcd, args)
+            }
+            BuiltinKind::Int(_) => return Err(invalid(&args)),
+        };
+        let ty = match func {
+            Func::Libm(_) => Type::Float,
+            _ => args[0].ty,
+        };
+
+        Ok(TExpr::new(TExprKind::Call(func, args), ty))
+    }
+
+    // The result of `?:` can have different types only if it's calculated
+    fn type_cond(
+        &mut self,
+        Cond {
+            cond,
+            if_true,
+            if_false,
+        }: &Cond,
+        span: Span,
+    ) -> TypeRes<'a> {
+        let cond_span = cond.span;
+        let cond = self.type_expr(cond)?;
+        if cond.ty != Type::Bool {
+            return Err(self.invalid_operand("?:".to_string(), cond.ty.to_string(), cond_span));
+        }
+
+        let (if_true, if_false) = (self.type_expr(if_true)?, self.type_expr(if_false)?);
+        if if_true.ty != if_false.ty {
+            let err = self
+                .diag_ctxt
+                .handle()
+                .struct_err(MismatchedBranchTypes::new(
+                    if_true.ty.to_string(),
+                    if_false.ty.to_string(),
+                    span,
+                ))
+                .with_help(format!(
+                    "both branches must have the same type to be compiled to {}",
+                    self.target
+                ));
+            err.emit();
+
+            return Err(err);
+        }
+        let ty = if_true.ty;
+
+        Ok(TExpr::new(
+            TExprKind::Cond(Box::new(cond), Box::new(if_true), Box::new(if_false)),
+            ty,
+        ))
+    }
+
+    fn invalid_operand(&self, op: String, found: String, span: Span) -> Diagnostic<'a> {
+        self.diag_ctxt
+            .handle()
+            .emit_err(InvalidOperandCodegen::new(op, found, span))
+    }
+}
+
+// Converts integers to floats if there is at least one float, as the calculation does
+fn unify(exprs: Vec<TExpr>) -> Vec<TExpr> {
+    if exprs.iter().any(|expr| expr.ty == Type::Float) {
+        exprs.into_iter().map(TExpr::into_float).collect()
+    } else {
+        exprs
+    }
+}
+
+fn unify_pair(lhs: TExpr, rhs: TExpr) -> (TExpr, TExpr) {
+    let mut exprs = unify(vec![lhs, rhs]).into_iter();
+    (exprs.next().unwrap(), exprs.next().unwrap())
+}
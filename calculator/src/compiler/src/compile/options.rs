@@ -0,0 +1,68 @@
+/// Settings of the compilation that the user can choose.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CompileOptions {
+    pub num_mode: NumMode,
+    pub mixing: MixingPolicy,
+    pub int_div: IntDivision,
+    pub emit: Emit,
+    pub backend: Backend,
+}
+
+/// How the numbers of the program are represented.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum NumMode {
+    /// 64-bit integers and floats
+    #[default]
+    Native,
+    /// Arbitrary-precision integers and decimals, so the results are exact
+    Big,
+}
+
+/// What happens when integers and floats are used in the same operation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MixingPolicy {
+    /// Integers are converted to floats with a warning
+    #[default]
+    Promote,
+    /// Mixing is an error
+    Strict,
+    /// Integers are converted to floats without a warning
+    Silent,
+}
+
+/// What is the result of dividing an integer by an integer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum IntDivision {
+    /// Integer rounded toward zero, so `7 / 2` is `3`
+    #[default]
+    Truncate,
+    /// Float, so `7 / 2` is `3.5`
+    Float,
+}
+
+/// What is printed after the program is compiled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Emit {
+    /// Result of the program
+    #[default]
+    Result,
+    /// Program after constant folding and simplification
+    Simplified,
+    /// Standalone C source that prints the result
+    C,
+    /// Standalone x86-64 assembly in the GNU syntax that prints the result
+    Asm,
+    /// Textual LLVM IR with a `main` function that prints the result
+    Llvm,
+}
+
+/// How the program is calculated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Backend {
+    /// Walk the lowered ast recursively
+    #[default]
+    Tree,
+    /// Compile the lowered ast to bytecode and execute it on a stack machine,
+    /// so deeply nested expressions don't overflow the stack
+    Vm,
+}
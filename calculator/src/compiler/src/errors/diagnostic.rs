@@ -1,9 +1,15 @@
-use std::{borrow::Cow, fmt::Debug};
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    fmt::Debug,
+    rc::Rc,
+};
 
 use super::emitter::DynEmitter;
+use crate::span::{SourceFile, Span};
 
 pub trait IntoDiagnostic<'a> {
-    fn into_diag(&self, diag_ctxt: &'a DiagnosticCtxt) -> Diagnostic<'a>;
+    fn into_diag(self, diag_ctxt: &'a DiagnosticCtxt) -> Diagnostic<'a>;
 }
 
 /// The main struct for diagnostics. Its used to store errors in `DiagnosticMsg`
@@ -12,6 +18,11 @@ pub trait IntoDiagnostic<'a> {
 pub struct Diagnostic<'a> {
     diag_ctxt: &'a DiagnosticCtxt,
     diag_msg: DiagnosticMsg<'a>,
+    // Code that caused the diagnostic. Some diagnostics are not related
+    // to the source code (for example, if we failed to open a file), so they dont have it.
+    span: Option<Span>,
+    // Additional message that tells the user how to fix the error
+    help: Option<String>,
 }
 
 /// The struct to store diagnostic parameters such as emitter, etc.
@@ -19,6 +30,11 @@ pub struct Diagnostic<'a> {
 #[derive(Debug)]
 pub struct DiagnosticCtxt {
     emitter: Box<DynEmitter>,
+    // Source of the program that is being compiled now, used to display spans
+    source_file: RefCell<Option<Rc<SourceFile>>>,
+    // Number of emitted errors and warnings
+    err_count: Cell<usize>,
+    warn_count: Cell<usize>,
 }
 
 /// This structure is the main handler for managing diagnostics,
@@ -37,26 +53,87 @@ impl<'a> Diagnostic<'a> {
         Self {
             diag_ctxt,
             diag_msg,
+            span: None,
+            help: None,
         }
     }
 
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
     pub fn emit(&self) {
-        self.diag_ctxt.emitter.emit_diag(&self.diag_msg);
+        self.diag_ctxt.bump_err_count();
+        self.diag_ctxt.emitter.emit_diag(self);
     }
 
     pub fn emit_warn(&self) {
-        self.diag_ctxt.emitter.emit_warn(&self.diag_msg);
+        self.diag_ctxt.bump_warn_count();
+        self.diag_ctxt.emitter.emit_warn(self);
+    }
+
+    pub fn get_msg(&self) -> String {
+        self.diag_msg.get_msg()
+    }
+
+    pub fn get_span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub fn get_help(&self) -> Option<&str> {
+        self.help.as_deref()
+    }
+
+    pub fn get_source_file(&self) -> Option<Rc<SourceFile>> {
+        self.diag_ctxt.get_source_file()
     }
 }
 
 impl DiagnosticCtxt {
     pub fn new(emitter: Box<DynEmitter>) -> Self {
-        Self { emitter }
+        Self {
+            emitter,
+            source_file: RefCell::new(None),
+            err_count: Cell::new(0),
+            warn_count: Cell::new(0),
+        }
     }
 
     pub fn handle<'a>(&'a self) -> DiagnosticHandler<'a> {
         DiagnosticHandler::new(self)
     }
+
+    /// Sets the source of the program that is being compiled now.
+    /// Spans of all next diagnostics will point to this source.
+    pub fn set_source_file(&self, source_file: SourceFile) {
+        *self.source_file.borrow_mut() = Some(Rc::new(source_file));
+    }
+
+    pub fn get_source_file(&self) -> Option<Rc<SourceFile>> {
+        self.source_file.borrow().clone()
+    }
+
+    pub fn err_count(&self) -> usize {
+        self.err_count.get()
+    }
+
+    pub fn warn_count(&self) -> usize {
+        self.warn_count.get()
+    }
+
+    fn bump_err_count(&self) {
+        self.err_count.set(self.err_count.get() + 1);
+    }
+
+    fn bump_warn_count(&self) {
+        self.warn_count.set(self.warn_count.get() + 1);
+    }
 }
 
 impl<'a> DiagnosticHandler<'a> {
@@ -77,7 +154,7 @@ impl<'a> DiagnosticHandler<'a> {
 
     // Create a `Diagnostic` from error that we can emit/return later
     pub fn struct_err(self, err: impl IntoDiagnostic<'a>) -> Diagnostic<'a> {
-        err.into_diag(&self.diag_ctxt)
+        err.into_diag(self.diag_ctxt)
     }
 }
 
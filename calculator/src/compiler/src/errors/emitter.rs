@@ -1,35 +1,163 @@
-use super::diagnostic::DiagnosticMsg;
+#[cfg(test)]
+mod tests;
+
+use std::{cell::RefCell, rc::Rc};
+
+use super::diagnostic::Diagnostic;
+use crate::{
+    helpers::{
+        json::{json_opt_string, json_string},
+        output::Output,
+    },
+    span::{SourceFile, Span},
+};
 
 pub type DynEmitter = dyn Emitter;
 
 pub trait Emitter: std::fmt::Debug {
-    fn emit_diag(&self, diag_msg: &DiagnosticMsg);
-    fn emit_warn(&self, diag_msg: &DiagnosticMsg);
+    fn emit_diag(&self, diag: &Diagnostic);
+    fn emit_warn(&self, diag: &Diagnostic);
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub struct StdoutEmitter;
+/// Emitter that prints diagnostics for humans with the code they point to.
+/// They are printed to stdout unless another `Output` is set.
+#[derive(Debug, Clone, Default)]
+pub struct StdoutEmitter {
+    out: Output,
+}
 
 impl StdoutEmitter {
     pub fn new() -> Self {
-        StdoutEmitter
+        Self::default()
+    }
+
+    pub fn with_output(mut self, out: Output) -> Self {
+        self.out = out;
+        self
+    }
+
+    fn emit(&self, diag: &Diagnostic) {
+        writeln!(self.out, "Error: {}", diag.get_msg());
+        self.emit_snippet(diag);
     }
 
-    fn emit(&self, diag_msg: &DiagnosticMsg) {
-        println!("Error: {}", diag_msg.get_msg());
+    fn emit_warn(&self, diag: &Diagnostic) {
+        writeln!(self.out, "Warning: {}", diag.get_msg());
+        self.emit_snippet(diag);
     }
 
-    fn emit_warn(&self, diag_msg: &DiagnosticMsg) {
-        println!("Warning: {}", diag_msg.get_msg())
+    fn emit_snippet(&self, diag: &Diagnostic) {
+        if let (Some(span), Some(source_file)) = (diag.get_span(), diag.get_source_file()) {
+            write!(self.out, "{}", render_snippet(&source_file, span));
+        }
+        if let Some(help) = diag.get_help() {
+            writeln!(self.out, "help: {help}");
+        }
     }
 }
 
 impl Emitter for StdoutEmitter {
-    fn emit_diag(&self, diag_msg: &DiagnosticMsg) {
-        self.emit(diag_msg);
+    fn emit_diag(&self, diag: &Diagnostic) {
+        self.emit(diag);
+    }
+
+    fn emit_warn(&self, diag: &Diagnostic) {
+        self.emit_warn(diag);
+    }
+}
+
+/// Emitter that ignores all diagnostics, for example, when we only need to know
+/// whether the code has any of them.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SilentEmitter;
+
+impl Emitter for SilentEmitter {
+    fn emit_diag(&self, _: &Diagnostic) {}
+
+    fn emit_warn(&self, _: &Diagnostic) {}
+}
+
+/// Diagnostics that were emitted by `JsonEmitter`, every one is a JSON object.
+pub type JsonDiags = Rc<RefCell<Vec<String>>>;
+
+/// Emitter that stores diagnostics as JSON objects instead of printing them,
+/// so they can be printed together with the result of the program.
+#[derive(Debug, Clone, Default)]
+pub struct JsonEmitter {
+    diags: JsonDiags,
+}
+
+impl JsonEmitter {
+    pub fn new(diags: JsonDiags) -> Self {
+        Self { diags }
+    }
+}
+
+impl Emitter for JsonEmitter {
+    fn emit_diag(&self, diag: &Diagnostic) {
+        self.diags.borrow_mut().push(render_json(diag, "error"));
     }
 
-    fn emit_warn(&self, diag_msg: &DiagnosticMsg) {
-        self.emit_warn(diag_msg);
+    fn emit_warn(&self, diag: &Diagnostic) {
+        self.diags.borrow_mut().push(render_json(diag, "warning"));
     }
 }
+
+/// Renders the diagnostic as a JSON object. Span is `null` if the diagnostic
+/// is not related to the source code, otherwise it has its line and column:
+/// ```text
+/// {"level":"error","message":"...","span":{"lo":4,"hi":5,"line":1,"col":5},"help":null}
+/// ```
+pub fn render_json(diag: &Diagnostic, level: &str) -> String {
+    let span = match (diag.get_span(), diag.get_source_file()) {
+        (Some(span), Some(source_file)) => {
+            let loc = source_file.lookup_loc(span.lo);
+            format!(
+                "{{\"lo\":{},\"hi\":{},\"line\":{},\"col\":{}}}",
+                span.lo, span.hi, loc.line, loc.col
+            )
+        }
+        _ => "null".to_string(),
+    };
+
+    format!(
+        "{{\"level\":{},\"message\":{},\"span\":{span},\"help\":{}}}",
+        json_string(level),
+        json_string(&diag.get_msg()),
+        json_opt_string(diag.get_help())
+    )
+}
+
+/// Renders the location of `span` and the first line of the code it points to:
+/// ```text
+///  --> examples/int_add.calc:1:5
+///   |
+/// 1 | 1 + * 3
+///   |     ^
+/// ```
+pub fn render_snippet(source_file: &SourceFile, span: Span) -> String {
+    let lo = source_file.lookup_loc(span.lo);
+    let hi = source_file.lookup_loc(span.hi);
+    let line = source_file.get_line(lo.line).unwrap_or_default();
+
+    // If the span takes several lines, underline the first one to the end
+    let hi_col = if hi.line == lo.line {
+        hi.col
+    } else {
+        line.chars().count() + 1
+    };
+    // Always underline at least one char, even if the span is empty (for example, `Eof`)
+    let underline_len = hi_col.saturating_sub(lo.col).max(1);
+
+    let line_num = lo.line.to_string();
+    let pad = " ".repeat(line_num.len());
+
+    format!(
+        "{pad}--> {}:{}:{}\n{pad} |\n{line_num} | {line}\n{pad} | {}{}\n",
+        source_file.get_name(),
+        lo.line,
+        lo.col,
+        " ".repeat(lo.col - 1),
+        "^".repeat(underline_len),
+    )
+}
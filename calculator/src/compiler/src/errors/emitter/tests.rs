@@ -0,0 +1,80 @@
+use super::{render_json, render_snippet, JsonDiags, JsonEmitter, StdoutEmitter};
+use crate::{
+    errors::diagnostic::{Diagnostic, DiagnosticCtxt, DiagnosticMsg},
+    helpers::output::Output,
+    span::{SourceFile, Span},
+};
+
+#[test]
+fn test_render_snippet() {
+    let source_file = SourceFile::new("test.calc".to_string(), "let x = 1;\nx + * 3".to_string());
+
+    assert_eq!(
+        render_snippet(&source_file, Span::new(15, 16)),
+        " --> test.calc:2:5\n  |\n2 | x + * 3\n  |     ^\n"
+    );
+    // Empty span at the end of the file, for example, for `Eof`
+    assert_eq!(
+        render_snippet(&source_file, Span::new(18, 18)),
+        " --> test.calc:2:8\n  |\n2 | x + * 3\n  |        ^\n"
+    );
+}
+
+#[test]
+fn test_render_json() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(JsonEmitter::default()));
+    diag_ctxt.set_source_file(SourceFile::new(
+        "test.calc".to_string(),
+        "let x = 1;\nx + * 3".to_string(),
+    ));
+    let diag = Diagnostic::new(&diag_ctxt, DiagnosticMsg::new("unexpected `\"*\"`".into()))
+        .with_span(Span::new(15, 16))
+        .with_help("remove it");
+
+    assert_eq!(
+        render_json(&diag, "error"),
+        r#"{"level":"error","message":"unexpected `\"*\"`","span":{"lo":15,"hi":16,"line":2,"col":5},"help":"remove it"}"#
+    );
+    // Diagnostics that are not related to the code don't have a span
+    let diag = Diagnostic::new(&diag_ctxt, DiagnosticMsg::new("failed".into()));
+    assert_eq!(
+        render_json(&diag, "warning"),
+        r#"{"level":"warning","message":"failed","span":null,"help":null}"#
+    );
+}
+
+#[test]
+fn test_json_emitter() {
+    let diags = JsonDiags::default();
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(JsonEmitter::new(diags.clone())));
+
+    Diagnostic::new(&diag_ctxt, DiagnosticMsg::new("first".into())).emit_warn();
+    Diagnostic::new(&diag_ctxt, DiagnosticMsg::new("second".into())).emit();
+
+    assert_eq!(
+        diags.take(),
+        vec![
+            r#"{"level":"warning","message":"first","span":null,"help":null}"#,
+            r#"{"level":"error","message":"second","span":null,"help":null}"#,
+        ]
+    );
+    assert_eq!((diag_ctxt.err_count(), diag_ctxt.warn_count()), (1, 1));
+}
+
+#[test]
+fn test_buffered_emitter() {
+    let out = Output::buffer();
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(StdoutEmitter::new().with_output(out.clone())));
+    diag_ctxt.set_source_file(SourceFile::new("test.calc".to_string(), "1 + ".to_string()));
+
+    Diagnostic::new(&diag_ctxt, DiagnosticMsg::new("expected expression".into()))
+        .with_span(Span::new(4, 4))
+        .with_help("add an operand")
+        .emit();
+
+    assert_eq!(
+        out.take(),
+        "Error: expected expression\n --> test.calc:1:5\n  |\n1 | 1 + \n  |     ^\nhelp: add an operand\n"
+    );
+    assert!(out.take().is_empty());
+}
@@ -0,0 +1,237 @@
+#[cfg(test)]
+mod tests;
+
+use std::{cell::RefCell, fmt, rc::Rc};
+
+pub use bigdecimal::BigDecimal;
+pub use num_bigint::BigInt;
+
+pub use crate::compile::MAX_BIG_BITS;
+
+use crate::{
+    ast_lowering::ast::LiteralKind,
+    compile::{
+        Backend, Compile, CompileOptions, Emit, Env, IntDivision, MixingPolicy, NumMode, Program,
+    },
+    errors::{
+        diagnostic::{self, DiagnosticCtxt},
+        emitter::Emitter,
+    },
+    span::{Loc, Span},
+};
+
+/// Settings of `eval()`. Unlike `CompileOptions`, it has only the settings
+/// that change how the program is calculated, and not what the command line prints.
+/// They are set with `with_*` methods, so new settings can be added later:
+/// ```
+/// use compiler::{EvalOptions, NumMode};
+///
+/// let options = EvalOptions::default().with_num_mode(NumMode::Big);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EvalOptions {
+    num_mode: NumMode,
+    mixing: MixingPolicy,
+    int_div: IntDivision,
+    backend: Backend,
+}
+
+impl EvalOptions {
+    pub fn with_num_mode(mut self, num_mode: NumMode) -> Self {
+        self.num_mode = num_mode;
+        self
+    }
+
+    pub fn with_mixing(mut self, mixing: MixingPolicy) -> Self {
+        self.mixing = mixing;
+        self
+    }
+
+    pub fn with_int_div(mut self, int_div: IntDivision) -> Self {
+        self.int_div = int_div;
+        self
+    }
+
+    /// Both backends return the same results, it only chooses how the program is calculated
+    pub fn with_backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+}
+
+impl From<EvalOptions> for CompileOptions {
+    fn from(options: EvalOptions) -> Self {
+        let EvalOptions {
+            num_mode,
+            mixing,
+            int_div,
+            backend,
+        } = options;
+
+        CompileOptions {
+            num_mode,
+            mixing,
+            int_div,
+            emit: Emit::Result,
+            backend,
+        }
+    }
+}
+
+/// Result of a program calculated by `eval()`.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    /// Integers with `NumMode::Big`
+    BigInt(BigInt),
+    /// Floats with `NumMode::Big`
+    BigDecimal(BigDecimal),
+    Bool(bool),
+}
+
+impl From<LiteralKind> for Value {
+    fn from(kind: LiteralKind) -> Self {
+        match kind {
+            LiteralKind::Int { val } => Value::Int(val),
+            LiteralKind::Float { val } => Value::Float(val),
+            LiteralKind::BigInt { val } => Value::BigInt(val),
+            LiteralKind::BigDecimal { val } => Value::BigDecimal(val),
+            LiteralKind::Bool { val } => Value::Bool(val),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(val) => write!(f, "{val}"),
+            Value::Float(val) => write!(f, "{val}"),
+            Value::BigInt(val) => write!(f, "{val}"),
+            Value::BigDecimal(val) => write!(f, "{val}"),
+            Value::Bool(val) => write!(f, "{val}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+/// Diagnostic returned by `eval()`. Unlike `errors::diagnostic::Diagnostic`, it doesn't borrow
+/// `DiagnosticCtxt`, so it can be kept after the calculation or sent to another thread.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Diagnostic {
+    pub level: Level,
+    pub message: String,
+    /// Code that caused the diagnostic, some diagnostics are not related to it
+    pub span: Option<Span>,
+    /// Line and column of the start of `span`
+    pub loc: Option<Loc>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    fn new(diag: &diagnostic::Diagnostic, level: Level) -> Self {
+        let span = diag.get_span();
+        Self {
+            level,
+            message: diag.get_msg(),
+            span,
+            loc: span
+                .zip(diag.get_source_file())
+                .map(|(span, source_file)| source_file.lookup_loc(span.lo)),
+            help: diag.get_help().map(str::to_string),
+        }
+    }
+}
+
+// `error: message`, as in the first line of the text output
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.level {
+            Level::Error => "error",
+            Level::Warning => "warning",
+        };
+        write!(f, "{level}: {}", self.message)?;
+        if let Some(loc) = self.loc {
+            write!(f, " at {}:{}", loc.line, loc.col)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Keeps the emitted diagnostics until the calculation is finished
+#[derive(Debug, Default)]
+struct CollectEmitter {
+    diags: Rc<RefCell<Vec<Diagnostic>>>,
+}
+
+impl Emitter for CollectEmitter {
+    fn emit_diag(&self, diag: &diagnostic::Diagnostic) {
+        self.diags
+            .borrow_mut()
+            .push(Diagnostic::new(diag, Level::Error));
+    }
+
+    fn emit_warn(&self, diag: &diagnostic::Diagnostic) {
+        self.diags
+            .borrow_mut()
+            .push(Diagnostic::new(diag, Level::Warning));
+    }
+}
+
+/// Calculates the program and returns its result, or all its diagnostics if it failed:
+/// ```
+/// use compiler::{eval, EvalOptions, Value};
+///
+/// let options = EvalOptions::default();
+/// assert_eq!(eval("let x = 6; x * 7", &options), Ok(Value::Int(42)));
+/// assert_eq!(eval("1 / 0", &options).unwrap_err()[0].loc.unwrap().col, 1);
+/// ```
+/// Every call has its own diagnostic context, so it can be used from several threads at once.
+///
+/// It can calculate untrusted input: big numbers that would have more than `MAX_BIG_BITS` bits
+/// are reported as overflow instead of being calculated, and deeply nested programs
+/// don't overflow the stack.
+pub fn eval(src: &str, options: &EvalOptions) -> Result<Value, Vec<Diagnostic>> {
+    eval_with_warnings(src, options).map(|(value, _)| value)
+}
+
+/// Same as `eval()`, but also returns the warnings of the program if it didn't fail
+pub fn eval_with_warnings(
+    src: &str,
+    options: &EvalOptions,
+) -> Result<(Value, Vec<Diagnostic>), Vec<Diagnostic>> {
+    let emitter = CollectEmitter::default();
+    let diags = emitter.diags.clone();
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(emitter));
+
+    let res = Program::from_source_in(
+        src,
+        String::new(),
+        &diag_ctxt,
+        &Env::new(),
+        (*options).into(),
+    )
+    .and_then(|program| program.compile());
+    let mut diags = diags.take();
+
+    match res {
+        Ok(lit) => Ok((Value::from(lit.kind), diags)),
+        Err(err) => {
+            // Errors are emitted by the stages of compilation, but it's better
+            // to return the error twice than to return no error at all
+            if diags.iter().all(|diag| diag.level != Level::Error) {
+                diags.push(Diagnostic::new(&err, Level::Error));
+            }
+            Err(diags)
+        }
+    }
+}
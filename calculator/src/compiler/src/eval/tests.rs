@@ -0,0 +1,102 @@
+use std::{str::FromStr, thread};
+
+use super::{
+    eval, eval_with_warnings, BigDecimal, BigInt, Diagnostic, EvalOptions, Level, Value,
+    MAX_BIG_BITS,
+};
+use crate::{
+    compile::{Backend, MixingPolicy, NumMode},
+    span::{Loc, Span},
+};
+
+#[test]
+fn test_eval_values() {
+    let options = EvalOptions::default();
+
+    assert_eq!(eval("let x = 2; x ** 10", &options), Ok(Value::Int(1024)));
+    assert_eq!(eval("sqrt(16.0) + 0.5", &options), Ok(Value::Float(4.5)));
+    assert_eq!(eval("1 < 2 && !false", &options), Ok(Value::Bool(true)));
+
+    let big = EvalOptions::default().with_num_mode(NumMode::Big);
+    assert_eq!(
+        eval("2 ** 100", &big),
+        Ok(Value::BigInt(BigInt::from(2).pow(100)))
+    );
+    assert_eq!(
+        eval("0.1 + 0.2", &big),
+        Ok(Value::BigDecimal(BigDecimal::from_str("0.3").unwrap()))
+    );
+    assert_eq!(eval("0.1 + 0.2", &big).unwrap().to_string(), "0.3");
+
+    let vm = EvalOptions::default().with_backend(Backend::Vm);
+    assert_eq!(eval("let x = 3; x > 2 ? x : 0", &vm), Ok(Value::Int(3)));
+}
+
+#[test]
+fn test_eval_diagnostics() {
+    let options = EvalOptions::default();
+
+    assert_eq!(
+        eval("let x = 1;\nx + * 3", &options),
+        Err(vec![Diagnostic {
+            level: Level::Error,
+            message: "expected expression but found `BinOp(Mul)`".to_string(),
+            span: Some(Span::new(15, 16)),
+            loc: Some(Loc { line: 2, col: 5 }),
+            help: None,
+        }])
+    );
+
+    let errs = eval("let x = 0; x + 1 + y", &options).unwrap_err();
+    assert!(errs.iter().all(|err| err.level == Level::Error));
+    assert_eq!(
+        errs[0].to_string(),
+        "error: cannot find variable `y` in this scope at 1:20"
+    );
+}
+
+#[test]
+fn test_eval_warnings() {
+    let (value, warnings) = eval_with_warnings("1 + 0.5", &EvalOptions::default()).unwrap();
+    assert_eq!(value, Value::Float(1.5));
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].level, Level::Warning);
+
+    let strict = EvalOptions::default().with_mixing(MixingPolicy::Strict);
+    assert!(eval("1 + 0.5", &strict).is_err());
+}
+
+#[test]
+fn test_eval_threads() {
+    let handles: Vec<_> = (0..8)
+        .map(|i| thread::spawn(move || eval(&format!("{i} * 2"), &EvalOptions::default())))
+        .collect();
+
+    for (i, handle) in handles.into_iter().enumerate() {
+        assert_eq!(handle.join().unwrap(), Ok(Value::Int(i as i64 * 2)));
+    }
+}
+
+#[test]
+fn test_eval_deeply_nested() {
+    // `eval()` is called on threads with the default stack, it must not overflow it
+    let depth = 100_000;
+    let src = format!("let x = 1; {}x{}", "(x + ".repeat(depth), ")".repeat(depth));
+
+    for backend in [Backend::Tree, Backend::Vm] {
+        let options = EvalOptions::default().with_backend(backend);
+        assert_eq!(eval(&src, &options), Ok(Value::Int(depth as i64 + 1)));
+    }
+}
+
+#[test]
+fn test_eval_too_large_big_numbers() {
+    let big = EvalOptions::default().with_num_mode(NumMode::Big);
+
+    let errs = eval("2 ** 4000000000", &big).unwrap_err();
+    assert_eq!(
+        errs[0].message,
+        "attempt to compute `2 ** 4000000000`, which would overflow"
+    );
+    assert!(eval(&format!("2 ** {}", MAX_BIG_BITS / 2), &big).is_ok());
+}
@@ -1 +1,5 @@
+pub mod glob;
+pub mod json;
+pub mod output;
+pub mod stack;
 pub mod test;
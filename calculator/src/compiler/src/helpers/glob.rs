@@ -0,0 +1,201 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+/// Returns `true` if the path is a glob pattern, not a path to a file or a directory
+pub fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// Splits the pattern into the directory where the search starts, which is the part
+/// of the pattern before the first wildcard, and components of the pattern after it
+pub fn split_glob(pattern: &str) -> (PathBuf, Vec<String>) {
+    let mut root = PathBuf::new();
+    let mut comps = Vec::new();
+    for comp in Path::new(pattern).components() {
+        match comp {
+            Component::Normal(name) if comps.is_empty() && !is_glob(&name.to_string_lossy()) => {
+                root.push(name)
+            }
+            Component::Normal(name) => comps.push(name.to_string_lossy().to_string()),
+            _ if comps.is_empty() => root.push(comp),
+            // `..` and `.` after a wildcard are matched as names
+            _ => comps.push(comp.as_os_str().to_string_lossy().to_string()),
+        }
+    }
+    if root.as_os_str().is_empty() {
+        root.push(".");
+    }
+
+    (root, comps)
+}
+
+/// Returns sorted paths of files and directories in `root` that match components of the pattern,
+/// see `split_glob()`. `root` is not a pattern, so it can contain chars like `[` in its names.
+/// Components can contain `*` (any chars), `?` (one char) and `[a-z]`
+/// (one char from the set, `[!a-z]` is any other char), `**` matches any number of directories.
+/// As in shells, wildcards don't match names that start with `.`.
+pub fn expand_glob(root: &Path, comps: &[String]) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    if comps.is_empty() {
+        if root.exists() {
+            paths.push(root.to_path_buf());
+        }
+    } else {
+        walk(root, comps, &mut paths)?;
+    }
+    paths.sort();
+    // `**` can match the same path several times, like in `a/**/**/b`
+    paths.dedup();
+
+    Ok(paths)
+}
+
+fn walk(dir: &Path, comps: &[String], paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    let Some((comp, rest)) = comps.split_first() else {
+        paths.push(dir.to_path_buf());
+        return Ok(());
+    };
+
+    if comp == "**" {
+        walk(dir, rest, paths)?;
+        for entry in dir.read_dir()? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() && !is_hidden(&entry.file_name().to_string_lossy()) {
+                walk(&entry.path(), comps, paths)?;
+            }
+        }
+        return Ok(());
+    }
+
+    for entry in dir.read_dir()? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if is_hidden(&name) && !comp.starts_with('.') || !matches(comp, &name) {
+            continue;
+        }
+
+        if rest.is_empty() {
+            paths.push(entry.path());
+        } else if entry.file_type()?.is_dir() {
+            walk(&entry.path(), rest, paths)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn is_hidden(name: &str) -> bool {
+    name.starts_with('.')
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Char(char),
+    // `?`
+    Any,
+    // `*`
+    Star,
+    // `[a-z0]` has ranges `('a', 'z')` and `('0', '0')`
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+/// Returns `true` if the whole `name` matches the pattern of one path component
+pub fn matches(pattern: &str, name: &str) -> bool {
+    let tokens = tokenize(pattern);
+    let name: Vec<char> = name.chars().collect();
+    let (mut t, mut n) = (0, 0);
+    // Position of the last `*` and the char of the name where it started to match,
+    // so we can backtrack and let it match one more char
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match tokens.get(t) {
+            Some(Token::Star) => {
+                star = Some((t, n));
+                t += 1;
+            }
+            Some(token) if token.matches(name[n]) => {
+                t += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_t, star_n)) => {
+                    t = star_t + 1;
+                    n = star_n + 1;
+                    star = Some((star_t, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    tokens[t..].iter().all(|token| *token == Token::Star)
+}
+
+impl Token {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Token::Char(expected) => *expected == c,
+            Token::Any => true,
+            Token::Star => false,
+            Token::Class { negated, ranges } => {
+                ranges.iter().any(|(lo, hi)| (*lo..=*hi).contains(&c)) != *negated
+            }
+        }
+    }
+}
+
+fn tokenize(pattern: &str) -> Vec<Token> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let token = match chars[i] {
+            '*' => Token::Star,
+            '?' => Token::Any,
+            '[' => match parse_class(&chars[i + 1..]) {
+                Some((token, len)) => {
+                    i += len;
+                    token
+                }
+                // `[` without `]` is a usual char
+                None => Token::Char('['),
+            },
+            c => Token::Char(c),
+        };
+        tokens.push(token);
+        i += 1;
+    }
+
+    tokens
+}
+
+// Parses the class after `[` and returns it with its length, including `]`
+fn parse_class(chars: &[char]) -> Option<(Token, usize)> {
+    let negated = matches!(chars.first(), Some('!' | '^'));
+    let mut i = usize::from(negated);
+    let mut ranges = Vec::new();
+
+    // `]` right after `[` is a char of the class
+    while i < chars.len() && (chars[i] != ']' || ranges.is_empty()) {
+        let lo = chars[i];
+        if chars.get(i + 1) == Some(&'-') && chars.get(i + 2).is_some_and(|c| *c != ']') {
+            ranges.push((lo, chars[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((lo, lo));
+            i += 1;
+        }
+    }
+
+    (i < chars.len()).then_some((Token::Class { negated, ranges }, i + 1))
+}
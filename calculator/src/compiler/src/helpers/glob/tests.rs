@@ -0,0 +1,68 @@
+use std::{fs, path::PathBuf};
+
+use super::{expand_glob, is_glob, matches, split_glob};
+
+#[test]
+fn test_glob_matches() {
+    assert!(matches("*.calc", "int_add.calc"));
+    assert!(matches("*.calc", ".calc"));
+    assert!(matches("int_*_*.calc", "int_a_b_c.calc"));
+    assert!(matches("?nt_add.calc", "int_add.calc"));
+    assert!(matches("[a-j]nt*", "int_add.calc"));
+    assert!(matches("[!a-h]nt*", "int_add.calc"));
+    assert!(matches("[]x]", "]"));
+    assert!(matches("a[", "a["));
+    assert!(matches("**", "anything"));
+
+    assert!(!matches("*.calc", "int_add.c"));
+    assert!(!matches("?.calc", "ab.calc"));
+    assert!(!matches("[!i]nt*", "int_add.calc"));
+    assert!(!matches("int", "int_add"));
+}
+
+#[test]
+fn test_is_glob() {
+    assert!(is_glob("examples/**/*.calc"));
+    assert!(is_glob("a?.calc"));
+    assert!(!is_glob("./examples/int_add.calc"));
+}
+
+#[test]
+fn test_expand_glob() {
+    let dir = std::env::temp_dir().join(format!("calc-glob-{}", std::process::id()));
+    for file in [
+        "b.calc",
+        "a.calc",
+        "a.txt",
+        "sub/c.calc",
+        "sub/deep/d.calc",
+        ".hidden/e.calc",
+    ] {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "1").unwrap();
+    }
+    let expand = |pattern: &str| {
+        let (root, comps) = split_glob(&format!("{}/{pattern}", dir.display()));
+        expand_glob(&root, &comps)
+            .unwrap()
+            .into_iter()
+            .map(|path| path.strip_prefix(&dir).unwrap().to_path_buf())
+            .collect::<Vec<_>>()
+    };
+    let paths = |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+
+    assert_eq!(expand("*.calc"), paths(&["a.calc", "b.calc"]));
+    assert_eq!(expand("a.*"), paths(&["a.calc", "a.txt"]));
+    assert_eq!(
+        expand("**/*.calc"),
+        paths(&["a.calc", "b.calc", "sub/c.calc", "sub/deep/d.calc"])
+    );
+    assert_eq!(expand("**/**/d.calc"), paths(&["sub/deep/d.calc"]));
+    assert_eq!(expand("*/*.calc"), paths(&["sub/c.calc"]));
+    assert_eq!(expand(".*/*.calc"), paths(&[".hidden/e.calc"]));
+    assert_eq!(expand("sub/*"), paths(&["sub/c.calc", "sub/deep"]));
+    assert!(expand("*.rs").is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,29 @@
+use std::fmt::Write as _;
+
+/// Returns `s` as a JSON string: in quotes and with escaped special chars.
+pub fn json_string(s: &str) -> String {
+    let mut res = String::with_capacity(s.len() + 2);
+
+    res.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(res, "\\u{:04x}", c as u32);
+            }
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+
+    res
+}
+
+/// Returns `s` as a JSON string or `null` if there is no value.
+pub fn json_opt_string(s: Option<&str>) -> String {
+    s.map_or_else(|| "null".to_string(), json_string)
+}
@@ -0,0 +1,40 @@
+use std::{
+    cell::RefCell,
+    fmt::{self, Write},
+    rc::Rc,
+};
+
+/// Where the text of emitters and result writers is printed. It's printed to stdout,
+/// or to a buffer to print it later, for example, when programs are compiled on several threads
+/// and their output must not be mixed.
+#[derive(Debug, Clone, Default)]
+pub enum Output {
+    #[default]
+    Stdout,
+    Buffer(Rc<RefCell<String>>),
+}
+
+impl Output {
+    pub fn buffer() -> Self {
+        Output::Buffer(Rc::default())
+    }
+
+    /// Returns the text printed to the buffer since the last call and clears it
+    pub fn take(&self) -> String {
+        match self {
+            Output::Stdout => String::new(),
+            Output::Buffer(buf) => buf.take(),
+        }
+    }
+
+    // Called by `write!()` and `writeln!()`
+    pub fn write_fmt(&self, args: fmt::Arguments) {
+        match self {
+            Output::Stdout => print!("{args}"),
+            Output::Buffer(buf) => buf
+                .borrow_mut()
+                .write_fmt(args)
+                .expect("Writing to a string can't fail"),
+        }
+    }
+}
@@ -0,0 +1,12 @@
+// Same limits as in rustc: if less than `RED_ZONE` of the stack is left,
+// a new segment of `STACK_PER_RECURSION` bytes is allocated
+const RED_ZONE: usize = 100 * 1024;
+const STACK_PER_RECURSION: usize = 1024 * 1024;
+
+/// Grows the stack on demand in recursive functions, so deeply nested expressions
+/// (like `((((1))))` with thousands of parentheses) don't overflow it.
+/// It should be called in every function that can be the start of a recursion.
+#[inline]
+pub fn ensure_sufficient_stack<R>(f: impl FnOnce() -> R) -> R {
+    stacker::maybe_grow(RED_ZONE, STACK_PER_RECURSION, f)
+}
@@ -13,7 +13,7 @@ pub struct IteratorDebug<T: IntoIterator + Debug>(T);
 #[derive(PartialEq, PartialOrd)]
 pub struct EnumDebug<T: Debug>(T);
 
-impl<'a, T> DebugHelper<T> {
+impl<T> DebugHelper<T> {
     pub const fn new_not_iterable(e: T) -> DebugHelper<EnumDebug<T>>
     where
         T: Debug,
@@ -33,7 +33,7 @@ impl<'a, T> DebugHelper<T> {
     }
 }
 
-impl<'a, T> Debug for DebugHelper<IteratorDebug<T>>
+impl<T> Debug for DebugHelper<IteratorDebug<T>>
 where
     T: Debug + IntoIterator<Item: Debug> + Clone,
 {
@@ -47,7 +47,7 @@ where
     }
 }
 
-impl<'a, T> Debug for DebugHelper<EnumDebug<T>>
+impl<T> Debug for DebugHelper<EnumDebug<T>>
 where
     T: Debug + Clone,
 {
@@ -1,4 +1,5 @@
 mod cursor;
+mod errors;
 #[cfg(test)]
 mod tests;
 pub mod token;
@@ -6,7 +7,10 @@ pub mod token;
 use std::collections::VecDeque;
 
 use crate::ast::{token as ast, TokenStream};
+use crate::errors::diagnostic::{Diagnostic, DiagnosticCtxt};
 use crate::lexer::cursor::Cursor;
+use crate::span::Span;
+use errors::{InvalidNumberLiteral, UnknownToken};
 use token::{LiteralKind, Token};
 
 pub const EOF_CHAR: char = '\0';
@@ -25,46 +29,193 @@ impl<'a> Lexer<'a> {
 
     fn next_token(&mut self) -> ast::Token {
         loop {
+            let lo = self.cursor.pos();
             let token = self.cursor.next_token();
+            let span = Span::new(lo, self.cursor.pos());
 
-            let token = match token {
+            let kind = match token {
                 Token::Lit { kind } => match kind {
-                    LiteralKind::Int { val } => ast::Token::Lit {
+                    LiteralKind::Int { val } => ast::TokenKind::Lit {
                         kind: ast::LiteralKind::Int { val },
                     },
-                    LiteralKind::Float { val } => ast::Token::Lit {
+                    LiteralKind::Float { val } => ast::TokenKind::Lit {
                         kind: ast::LiteralKind::Float { val },
                     },
                 },
-                Token::Star => ast::Token::BinOp(ast::BinOpKind::Mul),
-                Token::Slash => ast::Token::BinOp(ast::BinOpKind::Div),
-                Token::Percent => ast::Token::BinOp(ast::BinOpKind::Mod),
-                Token::Plus => ast::Token::BinOp(ast::BinOpKind::Add),
-                Token::Minus => ast::Token::BinOp(ast::BinOpKind::Sub),
-                Token::OpenParen => ast::Token::OpenParen,
-                Token::CloseParen => ast::Token::CloseParen,
+                Token::Ident { name } => match name.as_str() {
+                    "let" => ast::TokenKind::Let,
+                    "true" | "false" => ast::TokenKind::Lit {
+                        kind: ast::LiteralKind::Bool {
+                            val: name == "true",
+                        },
+                    },
+                    _ => ast::TokenKind::Ident { name },
+                },
+                Token::Star => ast::TokenKind::BinOp(ast::BinOpKind::Mul),
+                Token::StarStar => ast::TokenKind::BinOp(ast::BinOpKind::Pow),
+                Token::Slash => ast::TokenKind::BinOp(ast::BinOpKind::Div),
+                Token::Percent => ast::TokenKind::BinOp(ast::BinOpKind::Mod),
+                Token::Plus => ast::TokenKind::BinOp(ast::BinOpKind::Add),
+                Token::Minus => ast::TokenKind::BinOp(ast::BinOpKind::Sub),
+                Token::Amp => ast::TokenKind::BinOp(ast::BinOpKind::BitAnd),
+                Token::Pipe => ast::TokenKind::BinOp(ast::BinOpKind::BitOr),
+                Token::Caret => ast::TokenKind::BinOp(ast::BinOpKind::BitXor),
+                Token::Shl => ast::TokenKind::BinOp(ast::BinOpKind::Shl),
+                Token::Shr => ast::TokenKind::BinOp(ast::BinOpKind::Shr),
+                Token::Lt => ast::TokenKind::BinOp(ast::BinOpKind::Lt),
+                Token::Le => ast::TokenKind::BinOp(ast::BinOpKind::Le),
+                Token::Gt => ast::TokenKind::BinOp(ast::BinOpKind::Gt),
+                Token::Ge => ast::TokenKind::BinOp(ast::BinOpKind::Ge),
+                Token::EqEq => ast::TokenKind::BinOp(ast::BinOpKind::Eq),
+                Token::Ne => ast::TokenKind::BinOp(ast::BinOpKind::Ne),
+                Token::AmpAmp => ast::TokenKind::BinOp(ast::BinOpKind::And),
+                Token::PipePipe => ast::TokenKind::BinOp(ast::BinOpKind::Or),
+                Token::Tilde => ast::TokenKind::Tilde,
+                Token::Bang => ast::TokenKind::Bang,
+                Token::Question => ast::TokenKind::Question,
+                Token::Colon => ast::TokenKind::Colon,
+                Token::OpenParen => ast::TokenKind::OpenParen,
+                Token::CloseParen => ast::TokenKind::CloseParen,
+                Token::Eq => ast::TokenKind::Eq,
+                Token::Semi => ast::TokenKind::Semi,
+                Token::Comma => ast::TokenKind::Comma,
                 // Skip all whitespaces
                 Token::Whitespace => continue,
-                Token::Eof => ast::Token::Eof,
-                Token::Unknown { content } => ast::Token::Unknown { content },
+                Token::Eof => ast::TokenKind::Eof,
+                Token::Unknown { content } => ast::TokenKind::Unknown { content },
             };
 
-            break token;
+            break ast::Token::new(kind, span);
         }
     }
 
+    /// Returns all tokens of the input. The last token is always `Eof`,
+    /// so the parser knows where the input ends.
     pub fn token_stream(&mut self) -> TokenStream {
         let mut buf = Vec::new();
 
         loop {
-            match self.next_token() {
-                ast::Token::Eof => return TokenStream::new(VecDeque::from(buf)),
-                token => {
-                    buf.push(token);
-                }
+            let token = self.next_token();
+            let is_eof = token.kind == ast::TokenKind::Eof;
+            buf.push(token);
+
+            if is_eof {
+                return TokenStream::new(VecDeque::from(buf));
+            }
+        }
+    }
+}
+
+/// Reports the tokens that can't be a part of any program: unknown chars and malformed
+/// number literals. The lexer itself never fails, so this is a separate pass over its tokens.
+/// Unknown chars that look like some operator (for example, `×`) are replaced with it,
+/// so the parser doesn't report them again.
+pub fn check_token_stream<'a>(
+    token_stream: &mut TokenStream,
+    diag_ctxt: &'a DiagnosticCtxt,
+) -> Vec<Diagnostic<'a>> {
+    let mut errs = Vec::new();
+
+    for token in token_stream.iter_mut() {
+        match token.kind.clone() {
+            ast::TokenKind::Unknown { content } => {
+                let err = diag_ctxt
+                    .handle()
+                    .struct_err(UnknownToken::new(content.clone(), token.span));
+
+                errs.push(match lookalike_token(&content) {
+                    Some((kind, ascii)) => {
+                        token.kind = kind;
+                        err.with_help(format!("use `{ascii}` instead of `{content}`"))
+                    }
+                    None => err,
+                });
+            }
+            ast::TokenKind::Lit {
+                kind: ast::LiteralKind::Float { val },
+            } => {
+                let help = if val.matches('.').count() > 1 {
+                    "a number can contain only one point"
+                } else if val.ends_with('.') {
+                    "add a digit after the point, for example, `1.0`"
+                } else {
+                    continue;
+                };
+
+                errs.push(
+                    diag_ctxt
+                        .handle()
+                        .struct_err(InvalidNumberLiteral::new(val, token.span))
+                        .with_help(help),
+                );
             }
+            _ => {}
         }
     }
+
+    errs.iter().for_each(Diagnostic::emit);
+    errs
+}
+
+// Non-ASCII chars that users often paste instead of the operators
+fn lookalike_token(content: &str) -> Option<(ast::TokenKind, &'static str)> {
+    let mut chars = content.chars();
+    let ch = chars.next()?;
+    if chars.next().is_some() {
+        return None;
+    }
+
+    let lookalike = match ch {
+        '×' | '·' | '⋅' | '∗' => (ast::TokenKind::BinOp(ast::BinOpKind::Mul), "*"),
+        '÷' | '∕' => (ast::TokenKind::BinOp(ast::BinOpKind::Div), "/"),
+        '−' | '–' | '—' => (ast::TokenKind::BinOp(ast::BinOpKind::Sub), "-"),
+        '＋' => (ast::TokenKind::BinOp(ast::BinOpKind::Add), "+"),
+        '（' => (ast::TokenKind::OpenParen, "("),
+        '）' => (ast::TokenKind::CloseParen, ")"),
+        '＝' => (ast::TokenKind::Eq, "="),
+        '；' => (ast::TokenKind::Semi, ";"),
+        '，' => (ast::TokenKind::Comma, ","),
+        _ => return None,
+    };
+
+    Some(lookalike)
+}
+
+// Chars that are either a start of some known token or a whitespace
+fn is_token_start(c: char) -> bool {
+    c.is_ascii_digit()
+        || is_ident_start(c)
+        || is_whitespace(c)
+        || matches!(
+            c,
+            '+' | '-'
+                | '*'
+                | '/'
+                | '%'
+                | '&'
+                | '|'
+                | '^'
+                | '~'
+                | '<'
+                | '>'
+                | '!'
+                | '?'
+                | ':'
+                | '('
+                | ')'
+                | '='
+                | ';'
+                | ','
+                | EOF_CHAR
+        )
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_continue(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
 }
 
 fn is_whitespace(c: char) -> bool {
@@ -1,7 +1,7 @@
 use std::str::Chars;
 
 use super::{
-    is_whitespace,
+    is_ident_continue, is_ident_start, is_token_start, is_whitespace,
     token::{LiteralKind, Token},
     EOF_CHAR,
 };
@@ -9,81 +9,123 @@ use super::{
 #[derive(Clone, Debug)]
 pub struct Cursor<'a> {
     input: Chars<'a>,
+    initial_len: usize,
 }
 
 impl<'a> Cursor<'a> {
     pub fn new(input: &'a str) -> Self {
         Self {
             input: input.chars(),
+            initial_len: input.len(),
         }
     }
 
+    /// Returns the byte offset of the next char in the input
+    pub fn pos(&self) -> usize {
+        self.initial_len - self.input.as_str().len()
+    }
+
     pub fn next_token(&mut self) -> Token {
         let ch = match self.eat_next() {
             Some(ch) => ch,
             None => return Token::Eof,
         };
 
-        let token = match ch {
+        match ch {
             '0'..='9' => self.eat_num(ch),
+            ch if is_ident_start(ch) => self.eat_ident(ch),
             '+' => Token::Plus,
             '-' => Token::Minus,
-            '*' => Token::Star,
+            '*' => match self.first() {
+                '*' => {
+                    self.eat_next();
+                    Token::StarStar
+                }
+                _ => Token::Star,
+            },
             '/' => Token::Slash,
             '%' => Token::Percent,
+            '&' => self.eat_double('&', Token::AmpAmp, Token::Amp),
+            '|' => self.eat_double('|', Token::PipePipe, Token::Pipe),
+            '^' => Token::Caret,
+            '~' => Token::Tilde,
+            '<' => match self.first() {
+                '<' => self.eat_double('<', Token::Shl, Token::Lt),
+                _ => self.eat_double('=', Token::Le, Token::Lt),
+            },
+            '>' => match self.first() {
+                '>' => self.eat_double('>', Token::Shr, Token::Gt),
+                _ => self.eat_double('=', Token::Ge, Token::Gt),
+            },
+            '!' => self.eat_double('=', Token::Ne, Token::Bang),
+            '?' => Token::Question,
+            ':' => Token::Colon,
             '(' => Token::OpenParen,
             ')' => Token::CloseParen,
+            '=' => self.eat_double('=', Token::EqEq, Token::Eq),
+            ';' => Token::Semi,
+            ',' => Token::Comma,
             EOF_CHAR => Token::Eof,
             ch if is_whitespace(ch) => self.whitespace(),
             _ => self.unknown(ch),
-        };
-
-        token
+        }
     }
 
     fn eat_num(&mut self, first_digit: char) -> Token {
         let mut str_number = String::from(first_digit);
-        // Eat next digits if there are any
-        str_number.push_str(self.eat_next_digits().as_str());
+        // `e` is a digit of hexadecimal literals, so only decimal literals have an exponent
+        let has_prefix = first_digit == '0' && matches!(self.first(), 'x' | 'o' | 'b');
+
+        // Eat all letters, digits and points, even if they can't be in this literal
+        // (as in `0b12` or `1.2.3`), so the whole literal is reported as invalid later
+        while is_ident_continue(self.first()) || self.first() == '.' {
+            // We check above that there is a next char
+            let ch = self.eat_next().expect("Error while processing number");
+            str_number.push(ch);
+
+            // Sign of the exponent, as in `1.5e-3`
+            if !has_prefix && matches!(ch, 'e' | 'E') && matches!(self.first(), '+' | '-') {
+                str_number.push(self.eat_next().expect("Error while processing exponent"));
+            }
+        }
 
-        match self.first() {
-            '.' => {
-                // We check above that there is a point
-                str_number.push(self.eat_next().expect("Error while processing point"));
-                str_number.push_str(self.eat_next_digits().as_str());
+        let is_float = str_number.contains('.') || (!has_prefix && str_number.contains(['e', 'E']));
+        let kind = if is_float {
+            LiteralKind::Float { val: str_number }
+        } else {
+            LiteralKind::Int { val: str_number }
+        };
 
-                Token::Lit {
-                    kind: LiteralKind::Float { val: str_number },
-                }
-            }
-            _ => Token::Lit {
-                kind: LiteralKind::Int { val: str_number },
-            },
+        Token::Lit { kind }
+    }
+
+    // Returns `double` if the next char is `second` (for example, `&&` after `&`),
+    // otherwise `single`
+    fn eat_double(&mut self, second: char, double: Token, single: Token) -> Token {
+        if self.first() == second {
+            self.eat_next();
+            double
+        } else {
+            single
         }
     }
 
-    fn eat_next_digits(&mut self) -> String {
-        let mut str_number = String::new();
+    fn eat_ident(&mut self, first_ch: char) -> Token {
+        let mut name = String::from(first_ch);
 
-        loop {
-            match self.first() {
-                ch if ch.is_numeric() => {
-                    self.eat_next();
-                    str_number.push(ch)
-                }
-                _ => {
-                    break;
-                }
-            }
+        while is_ident_continue(self.first()) {
+            // We check above that there is a next char
+            name.push(self.eat_next().expect("Error while processing identifier"));
         }
 
-        str_number
+        Token::Ident { name }
     }
 
     fn unknown(&mut self, first_ch: char) -> Token {
         let mut content = String::from(first_ch);
 
-        while !is_whitespace(self.first()) {
+        // Stop before anything that can start a valid token, so in `2×3` only `×` is unknown
+        while !is_token_start(self.first()) {
             let ch = match self.eat_next() {
                 Some(ch) => ch,
                 None => return Token::Unknown { content },
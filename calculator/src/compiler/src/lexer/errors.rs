@@ -0,0 +1,31 @@
+use crate::errors::diagnostic::{Diagnostic, DiagnosticCtxt, DiagnosticMsg, IntoDiagnostic};
+use crate::span::Span;
+use calculator_macros::IntoDiagnostic;
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("unknown token `{}`")]
+pub struct UnknownToken {
+    content: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl UnknownToken {
+    pub fn new(content: String, span: Span) -> Self {
+        Self { content, span }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("invalid number literal `{}`")]
+pub struct InvalidNumberLiteral {
+    literal: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl InvalidNumberLiteral {
+    pub fn new(literal: String, span: Span) -> Self {
+        Self { literal, span }
+    }
+}
@@ -1,19 +1,17 @@
-use std::collections::VecDeque;
-
 use crate::{
-    ast::{
-        token::{BinOpKind, LiteralKind as AstLiteralKind, Token as AstToken},
-        TokenStream,
-    },
+    ast::token::{BinOpKind, LiteralKind as AstLiteralKind, TokenKind as AstToken},
+    errors::{diagnostic::DiagnosticCtxt, emitter::SilentEmitter},
     helpers::test::DebugHelper,
     lexer::{
+        check_token_stream,
         cursor::Cursor,
         token::{LiteralKind, Token},
         Lexer,
     },
+    span::Span,
 };
 
-fn tokenize(mut cursor: Cursor) -> impl Iterator<Item = Token> + use<'_> {
+fn tokenize(mut cursor: Cursor<'_>) -> impl Iterator<Item = Token> + use<'_> {
     std::iter::from_fn(move || {
         let token = cursor.next_token();
         if token != Token::Eof {
@@ -29,7 +27,6 @@ fn cursor_test() {
     let input = "123.4 * 5 + (6789.12345 - 600) #$ ~```~~~";
     let cursor = Cursor::new(input);
     let token_stream: Vec<_> = tokenize(cursor).collect();
-    let token_stream = token_stream;
 
     assert_eq!(
         DebugHelper::new_iterable(token_stream),
@@ -73,9 +70,13 @@ fn cursor_test() {
                 content: "#$".to_string()
             },
             Token::Whitespace,
+            Token::Tilde,
             Token::Unknown {
-                content: "~```~~~".to_string()
+                content: "```".to_string()
             },
+            Token::Tilde,
+            Token::Tilde,
+            Token::Tilde,
         ])
     )
 }
@@ -83,11 +84,11 @@ fn cursor_test() {
 #[test]
 fn lexer_token_stream_test() {
     let mut lexer = Lexer::new("123    + 54321 - (        1.123456789 ) * 3 / 1 % 10   #$^     @");
-    let result = lexer.token_stream();
+    let result: Vec<_> = lexer.token_stream().map(|token| token.kind).collect();
 
     assert_eq!(
         DebugHelper::new_iterable(result),
-        DebugHelper::new_iterable(TokenStream::new(VecDeque::from([
+        DebugHelper::new_iterable(vec![
             // `123 + 54321 -`
             AstToken::Lit {
                 kind: AstLiteralKind::Int {
@@ -130,12 +131,153 @@ fn lexer_token_stream_test() {
             },
             // `#$^`
             AstToken::Unknown {
-                content: "#$^".to_string()
+                content: "#$".to_string()
             },
+            AstToken::BinOp(BinOpKind::BitXor),
             // `@`
             AstToken::Unknown {
                 content: "@".to_string()
-            }
-        ])))
+            },
+            AstToken::Eof,
+        ])
+    );
+}
+
+#[test]
+fn lexer_spans_test() {
+    let mut lexer = Lexer::new("(12.5 **\n  x)");
+    let spans: Vec<_> = lexer.token_stream().map(|token| token.span).collect();
+
+    assert_eq!(
+        spans,
+        vec![
+            Span::new(0, 1),   // `(`
+            Span::new(1, 5),   // `12.5`
+            Span::new(6, 8),   // `**`
+            Span::new(11, 12), // `x`
+            Span::new(12, 13), // `)`
+            Span::new(13, 13), // `Eof`
+        ]
+    );
+}
+
+#[test]
+fn check_token_stream_test() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(SilentEmitter));
+    let mut token_stream = Lexer::new("2×3 ÷ 1.2.3 + 1. @ 4.5").token_stream();
+    let errs = check_token_stream(&mut token_stream, &diag_ctxt);
+
+    let errs: Vec<_> = errs
+        .iter()
+        .map(|err| (err.get_msg(), err.get_span().unwrap(), err.get_help()))
+        .collect();
+    assert_eq!(
+        errs,
+        vec![
+            (
+                "unknown token `×`".to_string(),
+                Span::new(1, 3),
+                Some("use `*` instead of `×`")
+            ),
+            (
+                "unknown token `÷`".to_string(),
+                Span::new(5, 7),
+                Some("use `/` instead of `÷`")
+            ),
+            (
+                "invalid number literal `1.2.3`".to_string(),
+                Span::new(8, 13),
+                Some("a number can contain only one point")
+            ),
+            (
+                "invalid number literal `1.`".to_string(),
+                Span::new(16, 18),
+                Some("add a digit after the point, for example, `1.0`")
+            ),
+            ("unknown token `@`".to_string(), Span::new(19, 20), None),
+        ]
+    );
+    assert_eq!(diag_ctxt.err_count(), 5);
+
+    // Lookalikes are replaced with the operators they look like
+    let kinds: Vec<_> = token_stream.take(3).map(|token| token.kind).collect();
+    assert_eq!(
+        DebugHelper::new_iterable(kinds),
+        DebugHelper::new_iterable(vec![
+            AstToken::Lit {
+                kind: AstLiteralKind::Int {
+                    val: "2".to_string(),
+                },
+            },
+            AstToken::BinOp(BinOpKind::Mul),
+            AstToken::Lit {
+                kind: AstLiteralKind::Int {
+                    val: "3".to_string(),
+                },
+            },
+        ])
+    );
+}
+
+#[test]
+fn number_literals_test() {
+    let input = "0x1e-3 1_000 1.5e-3 2E+8 0b12";
+    let token_stream: Vec<_> = tokenize(Cursor::new(input))
+        .filter(|token| *token != Token::Whitespace)
+        .collect();
+    let int = |val: &str| Token::Lit {
+        kind: LiteralKind::Int {
+            val: val.to_string(),
+        },
+    };
+    let float = |val: &str| Token::Lit {
+        kind: LiteralKind::Float {
+            val: val.to_string(),
+        },
+    };
+
+    assert_eq!(
+        token_stream,
+        vec![
+            // `e` is a digit of hexadecimal literals, so `-` is an operator here
+            int("0x1e"),
+            Token::Minus,
+            int("3"),
+            int("1_000"),
+            float("1.5e-3"),
+            float("2E+8"),
+            // Invalid digits are reported after lowering
+            int("0b12"),
+        ]
+    );
+}
+
+#[test]
+fn comparison_tokens_test() {
+    let input = "< <= << > >= >> == = != ! && & || | ? :";
+    let token_stream: Vec<_> = tokenize(Cursor::new(input))
+        .filter(|token| *token != Token::Whitespace)
+        .collect();
+
+    assert_eq!(
+        token_stream,
+        vec![
+            Token::Lt,
+            Token::Le,
+            Token::Shl,
+            Token::Gt,
+            Token::Ge,
+            Token::Shr,
+            Token::EqEq,
+            Token::Eq,
+            Token::Ne,
+            Token::Bang,
+            Token::AmpAmp,
+            Token::Amp,
+            Token::PipePipe,
+            Token::Pipe,
+            Token::Question,
+            Token::Colon,
+        ]
     );
 }
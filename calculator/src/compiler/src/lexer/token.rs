@@ -1,13 +1,53 @@
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Token {
     Lit { kind: LiteralKind },
+    Ident { name: String },
     Star,
+    // `**`
+    StarStar,
     Slash,
     Percent,
     Plus,
     Minus,
+    // `&`
+    Amp,
+    // `|`
+    Pipe,
+    // `^`
+    Caret,
+    // `~`
+    Tilde,
+    // `<<`
+    Shl,
+    // `>>`
+    Shr,
+    // `<`
+    Lt,
+    // `<=`
+    Le,
+    // `>`
+    Gt,
+    // `>=`
+    Ge,
+    // `==`
+    EqEq,
+    // `!=`
+    Ne,
+    // `!`
+    Bang,
+    // `&&`
+    AmpAmp,
+    // `||`
+    PipePipe,
+    // `?`
+    Question,
+    // `:`
+    Colon,
     OpenParen,
     CloseParen,
+    Eq,
+    Semi,
+    Comma,
     Whitespace,
     Eof,
     Unknown { content: String },
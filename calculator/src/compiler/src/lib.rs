@@ -3,7 +3,13 @@ mod ast_lowering;
 pub mod cli_launcher;
 mod compile;
 pub mod errors;
+pub mod eval;
 mod helpers;
 mod lexer;
 mod parser;
-mod program_sess;
+pub mod program_sess;
+mod repl;
+pub mod span;
+
+pub use compile::{Backend, CompileOptions, Emit, IntDivision, MixingPolicy, NumMode};
+pub use eval::{eval, EvalOptions, Value};
@@ -2,32 +2,50 @@ mod errors;
 #[cfg(test)]
 mod tests;
 
+use std::collections::HashSet;
+
 use crate::{
     ast::{
-        token::{BinOpKind, Token},
-        Ast, BinOp, Expr, Lit, Stmt, TokenStream,
+        token::{BinOpKind, Token, TokenKind, UnOpKind},
+        Ast, BinOp, Call, Cond, Expr, ExprKind, Ident, Let, Lit, Stmt, TokenStream, UnOp,
     },
     ast_lowering::{self, Lower},
+    compile::NumMode,
     errors::{
-        diagnostic::{Diagnostic, DiagnosticCtxt, DiagnosticHandler},
+        diagnostic::{Diagnostic, DiagnosticCtxt, DiagnosticHandler, IntoDiagnostic},
         ParseResult,
     },
-    lexer::Lexer,
+    helpers::stack::ensure_sufficient_stack,
+    lexer::{self, Lexer},
+    span::Span,
 };
-use errors::{ExpectedCloseParen, ExpectedExpr};
+use errors::{ExpectedCloseParen, ExpectedExpr, ExpectedIdent, UnexpectedToken};
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub struct TokenCursor {
     token_stream: TokenStream,
+    eof_span: Span,
 }
 
 impl TokenCursor {
     pub fn new(token_stream: TokenStream) -> Self {
-        Self { token_stream }
+        Self {
+            token_stream,
+            eof_span: Span::default(),
+        }
     }
 
+    // After the end of the stream always returns `Eof` with the span of the last `Eof` token
     fn advance(&mut self) -> Token {
-        self.token_stream.next().unwrap_or(Token::Eof)
+        match self.token_stream.next() {
+            Some(token) => {
+                if token.kind == TokenKind::Eof {
+                    self.eof_span = token.span;
+                }
+                token
+            }
+            None => Token::new(TokenKind::Eof, self.eof_span),
+        }
     }
 }
 
@@ -35,6 +53,9 @@ pub struct Parser<'a> {
     token_cursor: TokenCursor,
     diag_ctxt: &'a DiagnosticCtxt,
     cur_tok: Token,
+    // All errors that we've found. We don't stop on the first error,
+    // but synchronize and continue parsing to report as many errors as possible.
+    errs: Vec<Diagnostic<'a>>,
 }
 
 impl<'a> Parser<'a> {
@@ -42,7 +63,8 @@ impl<'a> Parser<'a> {
         Self {
             token_cursor,
             diag_ctxt,
-            cur_tok: Token::EmptyExpr,
+            cur_tok: Token::new(TokenKind::EmptyExpr, Span::default()),
+            errs: Vec::new(),
         }
     }
 
@@ -53,87 +75,353 @@ impl<'a> Parser<'a> {
         )
     }
 
-    pub fn lowering_parse(&mut self) -> Result<ast_lowering::ast::Ast, Diagnostic<'a>> {
-        Lower::new(self.diag_ctxt).lower(self.parse()?)
+    /// Parses and lowers the token stream. Variables from `bound` are already defined.
+    pub fn lowering_parse(
+        &mut self,
+        bound: HashSet<String>,
+        num_mode: NumMode,
+    ) -> Result<ast_lowering::ast::Ast, Diagnostic<'a>> {
+        Lower::new(self.diag_ctxt, bound, num_mode).lower(self.parse()?)
     }
 
+    /// Parses the whole token stream. If there were errors, they all are already emitted
+    /// and the first of them is returned.
     pub fn parse(&mut self) -> ParseResult<'a, Ast> {
-        let stmt = self.parse_stmt()?;
+        // The lexer doesn't report errors, so check its tokens before parsing
+        self.errs = lexer::check_token_stream(&mut self.token_cursor.token_stream, self.diag_ctxt);
+        // Load the first token of the stream
+        self.advance();
+        let mut stmts = vec![self.parse_stmt()];
+
+        loop {
+            match self.cur_tok.kind {
+                TokenKind::Semi => {
+                    self.advance();
+
+                    // Trailing `;` after the last statement is allowed
+                    if self.cur_tok.kind == TokenKind::Eof {
+                        break;
+                    }
+                    stmts.push(self.parse_stmt());
+                }
+                TokenKind::Eof => break,
+                // Already reported by the lexer check
+                TokenKind::Unknown { .. } => {
+                    self.skip_until(|kind| matches!(kind, TokenKind::Semi))
+                }
+                _ => {
+                    self.emit_err(UnexpectedToken::new(
+                        format!("{:?}", TokenKind::Semi),
+                        format!("{:?}", self.cur_tok.kind),
+                        self.cur_tok.span,
+                    ));
+                    // Skip the rest of the statement
+                    self.skip_until(|kind| matches!(kind, TokenKind::Semi));
+                }
+            }
+        }
+
+        match self.errs.is_empty() {
+            true => Ok(Ast::Stmts(stmts)),
+            false => Err(self.errs.remove(0)),
+        }
+    }
+
+    fn parse_stmt(&mut self) -> Stmt {
+        match self.cur_tok.kind {
+            TokenKind::Let => {
+                let let_span = self.advance().span;
+                self.parse_let(let_span)
+            }
+            _ => Stmt::Expr(self.parse_expr()),
+        }
+    }
+
+    // Parses `name = expr` after the `let` keyword
+    fn parse_let(&mut self, let_span: Span) -> Stmt {
+        let ident = match self.cur_tok.kind.clone() {
+            TokenKind::Ident { name } => Ident::new(name, self.advance().span),
+            kind => {
+                self.emit_err(ExpectedIdent::new(format!("{:?}", kind), self.cur_tok.span));
+                return self.recover_stmt(let_span);
+            }
+        };
+
+        if self.expect(TokenKind::Eq).is_none() {
+            self.emit_err(UnexpectedToken::new(
+                format!("{:?}", TokenKind::Eq),
+                format!("{:?}", self.cur_tok.kind),
+                self.cur_tok.span,
+            ));
+            return self.recover_stmt(let_span);
+        }
+
+        Stmt::Let(Let::new(ident, self.parse_expr()))
+    }
+
+    // `cond ? a : b` has the lowest precedence and is right associative,
+    // so `a ? b : c ? d : e` is `a ? b : (c ? d : e)`
+    fn parse_expr(&mut self) -> Expr {
+        ensure_sufficient_stack(|| {
+            let cond = self.parse_or();
+            if self.expect(TokenKind::Question).is_none() {
+                return cond;
+            }
+            let if_true = self.parse_expr();
+
+            if self.expect(TokenKind::Colon).is_none() {
+                self.emit_err(UnexpectedToken::new(
+                    format!("{:?}", TokenKind::Colon),
+                    format!("{:?}", self.cur_tok.kind),
+                    self.cur_tok.span,
+                ));
+                return Expr::new(ExprKind::Err, cond.span.to(if_true.span));
+            }
+            let if_false = self.parse_expr();
+            let span = cond.span.to(if_false.span);
+
+            Expr::new(ExprKind::Cond(Cond::new(cond, if_true, if_false)), span)
+        })
+    }
+
+    // Binary operators from the lowest precedence to the highest, as in Rust:
+    // `||`, `&&`, comparisons, `|`, `^`, `&`, `<<` and `>>`, `+` and `-`, `*`, `/` and `%`
+    fn parse_or(&mut self) -> Expr {
+        self.parse_binops(&[BinOpKind::Or], Self::parse_and)
+    }
+
+    fn parse_and(&mut self) -> Expr {
+        self.parse_binops(&[BinOpKind::And], Self::parse_comparison)
+    }
+
+    fn parse_comparison(&mut self) -> Expr {
+        self.parse_binops(
+            &[
+                BinOpKind::Lt,
+                BinOpKind::Le,
+                BinOpKind::Gt,
+                BinOpKind::Ge,
+                BinOpKind::Eq,
+                BinOpKind::Ne,
+            ],
+            Self::parse_bit_or,
+        )
+    }
+
+    fn parse_bit_or(&mut self) -> Expr {
+        self.parse_binops(&[BinOpKind::BitOr], Self::parse_bit_xor)
+    }
+
+    fn parse_bit_xor(&mut self) -> Expr {
+        self.parse_binops(&[BinOpKind::BitXor], Self::parse_bit_and)
+    }
+
+    fn parse_bit_and(&mut self) -> Expr {
+        self.parse_binops(&[BinOpKind::BitAnd], Self::parse_shift)
+    }
 
-        Ok(Ast::Stmt(stmt))
+    fn parse_shift(&mut self) -> Expr {
+        self.parse_binops(&[BinOpKind::Shl, BinOpKind::Shr], Self::parse_sum)
     }
 
-    fn parse_stmt(&mut self) -> ParseResult<'a, Stmt> {
-        let expr = self.parse_expr()?;
+    fn parse_sum(&mut self) -> Expr {
+        self.parse_binops(&[BinOpKind::Add, BinOpKind::Sub], Self::parse_term)
+    }
 
-        Ok(Stmt::Expr(expr))
+    fn parse_term(&mut self) -> Expr {
+        self.parse_binops(
+            &[BinOpKind::Mul, BinOpKind::Div, BinOpKind::Mod],
+            Self::parse_unary,
+        )
     }
 
-    fn parse_expr(&mut self) -> ParseResult<'a, Expr> {
-        let mut lhs = self.parse_term()?;
+    // Parses left associative operators `ops`, whose operands are parsed by `parse_operand`
+    fn parse_binops(&mut self, ops: &[BinOpKind], parse_operand: fn(&mut Self) -> Expr) -> Expr {
+        let mut lhs = parse_operand(self);
 
         loop {
-            match self.cur_tok.clone() {
-                Token::BinOp(kind) if BinOpKind::Sub == kind || BinOpKind::Add == kind => {
-                    let rhs = self.parse_term()?;
+            match self.cur_tok.kind {
+                TokenKind::BinOp(kind) if ops.contains(&kind) => {
+                    self.advance();
+                    let rhs = parse_operand(self);
 
-                    lhs = Expr::BinOp(BinOp::new(lhs, kind, rhs));
+                    lhs = self.mk_binop(lhs, kind, rhs);
                 }
-                _ => return Ok(lhs),
+                _ => return lhs,
             }
         }
     }
 
-    fn parse_term(&mut self) -> ParseResult<'a, Expr> {
-        let mut lhs = self.parse_factor()?;
+    // `+`, `-`, `~` and `!` before an operand are unary operators, so `2 * -3` is `2 * (-3)`
+    fn parse_unary(&mut self) -> Expr {
+        ensure_sufficient_stack(|| {
+            let kind = match self.cur_tok.kind {
+                TokenKind::BinOp(BinOpKind::Sub) => UnOpKind::Neg,
+                TokenKind::BinOp(BinOpKind::Add) => UnOpKind::Pos,
+                TokenKind::Tilde => UnOpKind::BitNot,
+                TokenKind::Bang => UnOpKind::Not,
+                _ => return self.parse_power(),
+            };
+            let op_span = self.advance().span;
+            let expr = self.parse_unary();
+            let span = op_span.to(expr.span);
 
-        loop {
-            match self.advance() {
-                Token::BinOp(kind)
-                    if BinOpKind::Div == kind
-                        || BinOpKind::Mul == kind
-                        || BinOpKind::Mod == kind =>
-                {
-                    let rhs = self.parse_factor()?;
-
-                    lhs = Expr::BinOp(BinOp::new(lhs, kind, rhs));
+            Expr::new(ExprKind::UnOp(UnOp::new(kind, expr)), span)
+        })
+    }
+
+    // Power binds tighter than unary operators and is right associative,
+    // so `-2 ** 3 ** 2` is `-(2 ** (3 ** 2))`
+    fn parse_power(&mut self) -> Expr {
+        let lhs = self.parse_factor();
+
+        match self.cur_tok.kind {
+            TokenKind::BinOp(BinOpKind::Pow) => {
+                self.advance();
+                // Exponent can have its own unary operator: `2 ** -1`
+                let rhs = self.parse_unary();
+
+                self.mk_binop(lhs, BinOpKind::Pow, rhs)
+            }
+            _ => lhs,
+        }
+    }
+
+    fn parse_factor(&mut self) -> Expr {
+        match self.cur_tok.kind.clone() {
+            TokenKind::Lit { kind } => {
+                Expr::new(ExprKind::Lit(Lit::new(kind)), self.advance().span)
+            }
+            TokenKind::Ident { name } => {
+                let ident = Ident::new(name, self.advance().span);
+
+                match self.cur_tok.kind {
+                    TokenKind::OpenParen => self.parse_call(ident),
+                    _ => {
+                        let span = ident.span;
+                        Expr::new(ExprKind::Ident(ident), span)
+                    }
                 }
-                _ => return Ok(lhs),
+            }
+            TokenKind::OpenParen => {
+                let open_paren = self.advance();
+                let mut expr = self.parse_expr();
+
+                match self.expect_close_paren() {
+                    // Parenthesized expression also includes its parens
+                    Some(close_paren) => expr.span = open_paren.span.to(close_paren.span),
+                    None => return Expr::new(ExprKind::Err, open_paren.span.to(expr.span)),
+                }
+                expr
+            }
+            // Already reported by the lexer check
+            TokenKind::Unknown { .. } => Expr::new(ExprKind::Err, self.advance().span),
+            kind => {
+                let span = self.cur_tok.span;
+                self.emit_err(ExpectedExpr::new(format!("{:?}", kind), span));
+
+                // If this token can continue the expression (for example, it's an operator
+                // as in `1 + * 2`), dont eat it, so the parser can continue from it
+                if !matches!(
+                    kind,
+                    TokenKind::BinOp(_)
+                        | TokenKind::Question
+                        | TokenKind::Colon
+                        | TokenKind::CloseParen
+                        | TokenKind::Semi
+                        | TokenKind::Eof
+                ) {
+                    self.advance();
+                }
+
+                Expr::new(ExprKind::Err, span)
             }
         }
     }
 
-    fn parse_factor(&mut self) -> ParseResult<'a, Expr> {
-        match self.advance() {
-            Token::Lit { kind } => Ok(Expr::Lit(Lit::new(kind))),
-            Token::OpenParen => {
-                let expr = self.parse_expr()?;
+    // Parses `(arg, ...)` after the function name
+    fn parse_call(&mut self, ident: Ident) -> Expr {
+        let lo = ident.span;
+        self.advance();
+
+        let mut args = Vec::new();
+        if self.cur_tok.kind != TokenKind::CloseParen {
+            loop {
+                args.push(self.parse_expr());
 
-                if !self.expect(Token::CloseParen) {
-                    return Err(self
-                        .handle()
-                        .emit_err(ExpectedCloseParen::new(format!("{:?}", self.cur_tok))));
+                if self.expect(TokenKind::Comma).is_none() {
+                    break;
                 }
-                Ok(expr)
             }
-            _ => Err(self
-                .handle()
-                .emit_err(ExpectedExpr::new(format!("{:?}", self.cur_tok)))),
+        }
+
+        match self.expect_close_paren() {
+            Some(close_paren) => Expr::new(
+                ExprKind::Call(Call::new(ident, args)),
+                lo.to(close_paren.span),
+            ),
+            None => Expr::new(ExprKind::Err, lo),
         }
     }
 
-    fn advance(&mut self) -> Token {
-        let token = self.token_cursor.advance();
+    // If there is no close paren, reports it and skips everything to the close paren
+    // of this expression, if there is one
+    fn expect_close_paren(&mut self) -> Option<Token> {
+        if let Some(close_paren) = self.expect(TokenKind::CloseParen) {
+            return Some(close_paren);
+        }
 
-        if token != Token::Eof {
-            self.cur_tok = token.clone();
+        // Unknown tokens are already reported by the lexer check
+        if !matches!(self.cur_tok.kind, TokenKind::Unknown { .. }) {
+            self.emit_err(ExpectedCloseParen::new(
+                format!("{:?}", self.cur_tok.kind),
+                self.cur_tok.span,
+            ));
         }
+        self.skip_until(|kind| matches!(kind, TokenKind::CloseParen | TokenKind::Semi));
+        self.expect(TokenKind::CloseParen);
 
-        token
+        None
     }
 
-    fn expect(&mut self, expected_tok: Token) -> bool {
-        self.cur_tok == expected_tok
+    // Skips the rest of the broken statement
+    fn recover_stmt(&mut self, lo: Span) -> Stmt {
+        self.skip_until(|kind| matches!(kind, TokenKind::Semi));
+
+        Stmt::Expr(Expr::new(ExprKind::Err, lo))
+    }
+
+    // Skips tokens until `cond` is true for the current token or until `Eof`
+    fn skip_until(&mut self, cond: impl Fn(&TokenKind) -> bool) {
+        while !cond(&self.cur_tok.kind) && self.cur_tok.kind != TokenKind::Eof {
+            self.advance();
+        }
+    }
+
+    // Emits the error and saves it, so we can return it after parsing
+    fn emit_err(&mut self, err: impl IntoDiagnostic<'a>) {
+        let err = self.handle().emit_err(err);
+        self.errs.push(err);
+    }
+
+    fn mk_binop(&self, lhs: Expr, kind: BinOpKind, rhs: Expr) -> Expr {
+        let span = lhs.span.to(rhs.span);
+
+        Expr::new(ExprKind::BinOp(BinOp::new(lhs, kind, rhs)), span)
+    }
+
+    // Moves to the next token and returns the previous one
+    fn advance(&mut self) -> Token {
+        std::mem::replace(&mut self.cur_tok, self.token_cursor.advance())
+    }
+
+    // Eats and returns the current token if it is `expected`
+    fn expect(&mut self, expected: TokenKind) -> Option<Token> {
+        if self.cur_tok.kind == expected {
+            Some(self.advance())
+        } else {
+            None
+        }
     }
 
     fn handle(&self) -> DiagnosticHandler<'a> {
@@ -1,15 +1,51 @@
 use crate::errors::diagnostic::{Diagnostic, DiagnosticCtxt, DiagnosticMsg, IntoDiagnostic};
+use crate::span::Span;
 use calculator_macros::IntoDiagnostic;
 
 #[derive(IntoDiagnostic)]
 #[diagnostic("expected close paren `)`, but found `{}`")]
 pub struct ExpectedCloseParen {
     unexpected: String,
+    #[primary_span]
+    span: Span,
 }
 
 impl ExpectedCloseParen {
-    pub fn new(unexpected: String) -> Self {
-        Self { unexpected }
+    pub fn new(unexpected: String, span: Span) -> Self {
+        Self { unexpected, span }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("expected identifier but found `{}`")]
+pub struct ExpectedIdent {
+    unexpected: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl ExpectedIdent {
+    pub fn new(unexpected: String, span: Span) -> Self {
+        Self { unexpected, span }
+    }
+}
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("expected `{}`, but found `{}`")]
+pub struct UnexpectedToken {
+    expected: String,
+    unexpected: String,
+    #[primary_span]
+    span: Span,
+}
+
+impl UnexpectedToken {
+    pub fn new(expected: String, unexpected: String, span: Span) -> Self {
+        Self {
+            expected,
+            unexpected,
+            span,
+        }
     }
 }
 
@@ -17,10 +53,12 @@ impl ExpectedCloseParen {
 #[diagnostic("expected expression but found `{}`")]
 pub struct ExpectedExpr {
     unexpected: String,
+    #[primary_span]
+    span: Span,
 }
 
 impl ExpectedExpr {
-    pub fn new(unexpected: String) -> Self {
-        Self { unexpected }
+    pub fn new(unexpected: String, span: Span) -> Self {
+        Self { unexpected, span }
     }
 }
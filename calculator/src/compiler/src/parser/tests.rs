@@ -3,29 +3,57 @@ use std::fmt::Debug;
 use super::Parser;
 use crate::{
     ast::{
-        token::{BinOpKind, LiteralKind},
-        Ast, BinOp, Expr, Lit, Stmt,
+        token::{BinOpKind, LiteralKind, UnOpKind},
+        Ast, BinOp, Call, Cond, Expr, ExprKind, Ident, Let, Lit, Stmt, UnOp,
     },
     errors::{
-        diagnostic::{DiagnosticCtxt, DiagnosticMsg},
-        emitter::Emitter,
+        diagnostic::{Diagnostic, DiagnosticCtxt},
+        emitter::{Emitter, SilentEmitter},
     },
     helpers::test::DebugHelper,
+    span::Span,
 };
 
 #[derive(Debug)]
 struct MockEmitter;
 
 impl Emitter for MockEmitter {
-    fn emit_diag(&self, diag_msg: &DiagnosticMsg) {
-        panic!("{:?}", diag_msg)
+    fn emit_diag(&self, diag: &Diagnostic) {
+        panic!("{:?}", diag.get_msg())
     }
 
-    fn emit_warn(&self, diag_msg: &DiagnosticMsg) {
-        panic!("{:?}", diag_msg)
+    fn emit_warn(&self, diag: &Diagnostic) {
+        panic!("{:?}", diag.get_msg())
     }
 }
 
+fn int(val: &str, lo: usize) -> Expr {
+    let kind = LiteralKind::Int {
+        val: val.to_string(),
+    };
+    Expr::new(ExprKind::Lit(Lit::new(kind)), Span::new(lo, lo + val.len()))
+}
+
+fn float(val: &str, lo: usize) -> Expr {
+    let kind = LiteralKind::Float {
+        val: val.to_string(),
+    };
+    Expr::new(ExprKind::Lit(Lit::new(kind)), Span::new(lo, lo + val.len()))
+}
+
+fn ident(name: &str, lo: usize) -> Ident {
+    Ident::new(name.to_string(), Span::new(lo, lo + name.len()))
+}
+
+fn binop(lhs: Expr, kind: BinOpKind, rhs: Expr, span: Span) -> Expr {
+    Expr::new(ExprKind::BinOp(BinOp::new(lhs, kind, rhs)), span)
+}
+
+fn unop(kind: UnOpKind, expr: Expr, lo: usize) -> Expr {
+    let span = Span::new(lo, expr.span.hi);
+    Expr::new(ExprKind::UnOp(UnOp::new(kind, expr)), span)
+}
+
 #[test]
 fn test_binop_parsing() {
     let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
@@ -33,20 +61,202 @@ fn test_binop_parsing() {
 
     assert_eq!(
         DebugHelper::new_not_iterable(parser.parse().unwrap()),
-        DebugHelper::new_not_iterable(Ast::Stmt(Stmt::Expr(Expr::BinOp(BinOp::new(
-            Expr::Lit(Lit::new(LiteralKind::Float {
-                val: "1.2345".to_string()
-            })),
+        DebugHelper::new_not_iterable(Ast::Stmts(vec![Stmt::Expr(binop(
+            float("1.2345", 0),
             BinOpKind::Mul,
-            Expr::BinOp(BinOp::new(
-                Expr::Lit(Lit::new(LiteralKind::Int {
-                    val: "2".to_string()
-                })),
-                BinOpKind::Add,
-                Expr::Lit(Lit::new(LiteralKind::Int {
-                    val: "3".to_string()
-                }))
+            // Span of the parenthesized expression includes its parens
+            binop(int("2", 10), BinOpKind::Add, int("3", 14), Span::new(9, 16)),
+            Span::new(0, 16)
+        ))]))
+    );
+}
+
+#[test]
+fn test_let_parsing() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let mut parser = Parser::from_source("let x = 2; x * 3;", &diag_ctxt);
+
+    assert_eq!(
+        DebugHelper::new_not_iterable(parser.parse().unwrap()),
+        DebugHelper::new_not_iterable(Ast::Stmts(vec![
+            Stmt::Let(Let::new(ident("x", 4), int("2", 8))),
+            Stmt::Expr(binop(
+                Expr::new(ExprKind::Ident(ident("x", 11)), Span::new(11, 12)),
+                BinOpKind::Mul,
+                int("3", 15),
+                Span::new(11, 16)
             ))
-        )))))
+        ]))
+    );
+}
+
+#[test]
+#[should_panic(expected = "expected `Semi`, but found `Lit")]
+fn test_missing_semi() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let _ = Parser::from_source("1 + 2 3", &diag_ctxt).parse();
+}
+
+#[test]
+fn test_unop_parsing() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let mut parser = Parser::from_source("2 * -+3 - -x", &diag_ctxt);
+
+    assert_eq!(
+        DebugHelper::new_not_iterable(parser.parse().unwrap()),
+        DebugHelper::new_not_iterable(Ast::Stmts(vec![Stmt::Expr(binop(
+            binop(
+                int("2", 0),
+                BinOpKind::Mul,
+                unop(UnOpKind::Neg, unop(UnOpKind::Pos, int("3", 6), 5), 4),
+                Span::new(0, 7)
+            ),
+            BinOpKind::Sub,
+            unop(
+                UnOpKind::Neg,
+                Expr::new(ExprKind::Ident(ident("x", 11)), Span::new(11, 12)),
+                10
+            ),
+            Span::new(0, 12)
+        ))]))
+    );
+}
+
+#[test]
+fn test_pow_parsing() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let mut parser = Parser::from_source("-2 ** 3 ** 2 * 4", &diag_ctxt);
+
+    assert_eq!(
+        DebugHelper::new_not_iterable(parser.parse().unwrap()),
+        DebugHelper::new_not_iterable(Ast::Stmts(vec![Stmt::Expr(binop(
+            unop(
+                UnOpKind::Neg,
+                binop(
+                    int("2", 1),
+                    BinOpKind::Pow,
+                    binop(int("3", 6), BinOpKind::Pow, int("2", 11), Span::new(6, 12)),
+                    Span::new(1, 12)
+                ),
+                0
+            ),
+            BinOpKind::Mul,
+            int("4", 15),
+            Span::new(0, 16)
+        ))]))
+    );
+}
+
+#[test]
+fn test_multiple_errors() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(SilentEmitter));
+    let err = Parser::from_source("(1 + * 2) * let + 3; let = 4; 5 6; (1 + 2; 7", &diag_ctxt)
+        .parse()
+        .unwrap_err();
+
+    // The first error is returned, but all of them are emitted
+    assert_eq!(err.get_msg(), "expected expression but found `BinOp(Mul)`");
+    assert_eq!(err.get_span(), Some(Span::new(5, 6)));
+    assert_eq!(diag_ctxt.err_count(), 5);
+}
+
+#[test]
+fn test_unknown_tokens_are_reported_once() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(SilentEmitter));
+    let err = Parser::from_source("(2 × 3 # 4; 5 $", &diag_ctxt)
+        .parse()
+        .unwrap_err();
+
+    assert_eq!(err.get_msg(), "unknown token `×`");
+    // Only lexer errors, the parser doesn't report unknown tokens again
+    assert_eq!(diag_ctxt.err_count(), 3);
+}
+
+#[test]
+fn test_call_parsing() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let mut parser = Parser::from_source("max(1, 2 * 3) + pi()", &diag_ctxt);
+
+    assert_eq!(
+        DebugHelper::new_not_iterable(parser.parse().unwrap()),
+        DebugHelper::new_not_iterable(Ast::Stmts(vec![Stmt::Expr(binop(
+            Expr::new(
+                ExprKind::Call(Call::new(
+                    ident("max", 0),
+                    vec![
+                        int("1", 4),
+                        binop(int("2", 7), BinOpKind::Mul, int("3", 11), Span::new(7, 12))
+                    ]
+                )),
+                Span::new(0, 13)
+            ),
+            BinOpKind::Add,
+            Expr::new(
+                ExprKind::Call(Call::new(ident("pi", 16), vec![])),
+                Span::new(16, 20)
+            ),
+            Span::new(0, 20)
+        ))]))
+    );
+}
+
+#[test]
+fn test_bitwise_precedence() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let mut parser = Parser::from_source("1 | 2 ^ 3 & ~4 << 1 + 1", &diag_ctxt);
+
+    let shl = binop(
+        unop(UnOpKind::BitNot, int("4", 13), 12),
+        BinOpKind::Shl,
+        binop(
+            int("1", 18),
+            BinOpKind::Add,
+            int("1", 22),
+            Span::new(18, 23),
+        ),
+        Span::new(12, 23),
+    );
+    let bit_and = binop(int("3", 8), BinOpKind::BitAnd, shl, Span::new(8, 23));
+    let bit_xor = binop(int("2", 4), BinOpKind::BitXor, bit_and, Span::new(4, 23));
+
+    assert_eq!(
+        DebugHelper::new_not_iterable(parser.parse().unwrap()),
+        DebugHelper::new_not_iterable(Ast::Stmts(vec![Stmt::Expr(binop(
+            int("1", 0),
+            BinOpKind::BitOr,
+            bit_xor,
+            Span::new(0, 23)
+        ))]))
+    );
+}
+
+#[test]
+fn test_cond_parsing() {
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(MockEmitter));
+    let mut parser = Parser::from_source("!a || 1 < 2 ? 3 : b ? 4 : 5", &diag_ctxt);
+    let word = |name, lo| {
+        let ident = ident(name, lo);
+        let span = ident.span;
+        Expr::new(ExprKind::Ident(ident), span)
+    };
+
+    let cond = binop(
+        unop(UnOpKind::Not, word("a", 1), 0),
+        BinOpKind::Or,
+        binop(int("1", 6), BinOpKind::Lt, int("2", 10), Span::new(6, 11)),
+        Span::new(0, 11),
+    );
+    // Conditional expression is right associative
+    let if_false = Expr::new(
+        ExprKind::Cond(Cond::new(word("b", 18), int("4", 22), int("5", 26))),
+        Span::new(18, 27),
+    );
+
+    assert_eq!(
+        DebugHelper::new_not_iterable(parser.parse().unwrap()),
+        DebugHelper::new_not_iterable(Ast::Stmts(vec![Stmt::Expr(Expr::new(
+            ExprKind::Cond(Cond::new(cond, int("3", 14), if_false)),
+            Span::new(0, 27)
+        ))]))
     );
 }
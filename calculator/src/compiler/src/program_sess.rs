@@ -1,136 +1,269 @@
-use std::{env, io, process::exit};
+#[cfg(test)]
+mod tests;
 
-use crate::{
-    ast_lowering::ast::LiteralKind,
-    compile::{Compile, Program, SOURCE_FILE_EXTENSION},
-    errors::diagnostic::{Diagnostic, DiagnosticCtxt},
+mod discover;
+mod errors;
+mod runner;
+mod writer;
+
+use std::{
+    collections::BTreeMap,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    process::exit,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
 };
 
+pub use crate::helpers::output::Output;
+use crate::{compile::CompileOptions, errors::diagnostic::DiagnosticCtxt, helpers::glob::is_glob};
+pub use discover::{find_programs, PathError};
+use runner::Runner;
+pub use writer::{JsonWriter, OutputFormat, ProgramReport, ResultWriter, TextWriter};
+
+/// Compiles programs from files and prints what happened with them with its `ResultWriter`.
+/// It works on absolute paths and doesn't change the current directory of the process,
+/// so it can be used as a library, for example:
+/// ```no_run
+/// use compiler::{
+///     errors::{diagnostic::DiagnosticCtxt, emitter::StdoutEmitter},
+///     program_sess::{ProgramSess, TextWriter},
+///     CompileOptions,
+/// };
+///
+/// let diag_ctxt = DiagnosticCtxt::new(Box::new(StdoutEmitter::new()));
+/// let psess = ProgramSess::from_dir(
+///     "examples",
+///     true,
+///     &diag_ctxt,
+///     CompileOptions::default(),
+///     Box::new(TextWriter::default()),
+/// )
+/// .unwrap();
+/// let failed = psess.run().iter().filter(|report| !report.passed).count();
+/// ```
 pub struct ProgramSess<'a> {
     diag_ctxt: &'a DiagnosticCtxt,
-    cur: std::path::PathBuf,
-    // if we have a specific file path, it will be `Some()`
-    // and `CalcSess::exec_with_display()` will be called. Otherwise, we will go through all the files
-    // in the current directory and execute them.
-    file_path: Option<std::path::PathBuf>,
-    has_program: bool,
+    // paths given by the user, they are shown if no program was found
+    paths: Vec<String>,
+    programs: Vec<PathBuf>,
+    // if the only path is a file, its result is printed without the summary
+    // and the session exits as soon as it fails
+    single_file: bool,
+    options: CompileOptions,
+    writer: Box<dyn ResultWriter>,
+    // if there is more than one job, programs are compiled on this number of threads
+    // with the writers of `make_writer`
+    jobs: NonZeroUsize,
+    make_writer: Option<WriterFactory<'a>>,
+    // where the buffered output of the threads is printed
+    out: Output,
 }
 
+/// Creates the diagnostic context and the writer of a thread of `ProgramSess::with_jobs()`,
+/// they must print everything to the given `Output`
+pub type WriterFactory<'a> =
+    Box<dyn Fn(Output) -> (DiagnosticCtxt, Box<dyn ResultWriter>) + Sync + 'a>;
+
 impl<'a> ProgramSess<'a> {
-    pub fn from_path(path: &str, diag_ctxt: &'a DiagnosticCtxt) -> Result<Self, io::Error> {
-        let path = std::path::Path::new(path);
-
-        match env::set_current_dir(path) {
-            Ok(()) => Ok({
-                Self {
-                    cur: env::current_dir()?,
-                    diag_ctxt,
-                    file_path: None,
-                    has_program: false,
-                }
-            }),
-            Err(err) if io::ErrorKind::NotADirectory == err.kind() && path.is_file() => {
-                // if its a file, then we can be sure that it has a parent
-                env::set_current_dir(
-                    path.parent()
-                        .expect("This is file does not contains a parent directory"),
-                )?;
-
-                Ok(Self {
-                    cur: env::current_dir()?,
-                    diag_ctxt,
-                    file_path: Some(env::current_dir()?.join(path.file_name().unwrap())),
-                    has_program: false,
-                })
-            }
-            Err(err) => Err(err),
-        }
+    /// Finds the programs of the paths, see `find_programs()`
+    pub fn from_paths(
+        paths: Vec<String>,
+        recursive: bool,
+        diag_ctxt: &'a DiagnosticCtxt,
+        options: CompileOptions,
+        writer: Box<dyn ResultWriter>,
+    ) -> Result<Self, PathError> {
+        let programs = find_programs(&paths, recursive)?;
+
+        Ok(Self::new(paths, programs, diag_ctxt, options, writer))
     }
 
-    pub fn run_with_exit(&mut self) {
-        let path = &self.file_path;
-        match path {
-            Some(path) => self.exec_with_exit(path.as_path()),
-            None => self.exec_many_with_exit(),
+    /// Compiles `programs` that were found by `find_programs()` in `paths`
+    pub fn new(
+        paths: Vec<String>,
+        programs: Vec<PathBuf>,
+        diag_ctxt: &'a DiagnosticCtxt,
+        options: CompileOptions,
+        writer: Box<dyn ResultWriter>,
+    ) -> Self {
+        let single_file =
+            matches!(paths.as_slice(), [path] if !is_glob(path) && Path::new(path).is_file());
+
+        Self {
+            diag_ctxt,
+            paths,
+            programs,
+            single_file,
+            options,
+            writer,
+            jobs: NonZeroUsize::MIN,
+            make_writer: None,
+            out: Output::Stdout,
         }
     }
 
-    fn exec_many_with_exit(&mut self) {
-        let cur = match self.read_cur_dir() {
-            Ok(cur) => cur,
-            Err(err) => {
-                println!(
-                    "Failed to read directory `{}`: {err}",
-                    self.get_cur_dir().display()
-                );
-                exit(1)
-            }
+    /// Compiles the programs of the directory, and of its subdirectories if `recursive`.
+    /// Unlike `from_paths()`, the path is never a glob pattern
+    pub fn from_dir(
+        dir: impl AsRef<Path>,
+        recursive: bool,
+        diag_ctxt: &'a DiagnosticCtxt,
+        options: CompileOptions,
+        writer: Box<dyn ResultWriter>,
+    ) -> Result<Self, PathError> {
+        let dir = dir.as_ref();
+        let path_err = |err| PathError {
+            path: dir.to_string_lossy().to_string(),
+            err,
         };
+        let programs =
+            discover::find_in_dir(&std::path::absolute(dir).map_err(path_err)?, recursive)
+                .map_err(path_err)?;
 
-        for file in cur {
-            let path = match file {
-                Ok(file) => file,
-                Err(err) => {
-                    println!(
-                        "Failed to open directory `{}`: {err}",
-                        self.get_cur_dir().display()
-                    );
-                    exit(1)
-                }
-            }
-            .path();
+        Ok(Self {
+            diag_ctxt,
+            paths: vec![dir.to_string_lossy().to_string()],
+            programs,
+            single_file: false,
+            options,
+            writer,
+            jobs: NonZeroUsize::MIN,
+            make_writer: None,
+            out: Output::Stdout,
+        })
+    }
 
-            if self.is_valid_file(path.as_path()) {
-                self.has_program = true;
-                self.exec_with_exit(path.as_path());
-            }
-        }
+    /// Compiles programs on `jobs` threads. Threads can't share the diagnostic context
+    /// and the writer of the session, so every thread creates its own ones with `make_writer`
+    /// that print to a buffer, and the output of every program is printed when
+    /// all the programs before it are printed. The summary is still printed by the writer
+    /// of the session.
+    pub fn with_jobs(
+        mut self,
+        jobs: NonZeroUsize,
+        make_writer: impl Fn(Output) -> (DiagnosticCtxt, Box<dyn ResultWriter>) + Sync + 'a,
+    ) -> Self {
+        self.jobs = jobs;
+        self.make_writer = Some(Box::new(make_writer));
+        self
+    }
+
+    /// Where the output of the threads of `with_jobs()` is printed, stdout by default
+    pub fn with_output(mut self, out: Output) -> Self {
+        self.out = out;
+        self
+    }
 
-        if self.has_program == false {
-            println!(
-                "Warning: not a single program was found in the directory `{}`",
-                self.get_cur_dir().display()
-            );
+    /// Programs of the session in the order they are compiled
+    pub fn programs(&self) -> &[PathBuf] {
+        &self.programs
+    }
+
+    /// Compiles every program, even if the previous ones failed, and returns what happened
+    /// with them. Unlike `run_with_exit()`, it doesn't print the summary and doesn't exit
+    pub fn run(&self) -> Vec<ProgramReport> {
+        match &self.make_writer {
+            Some(make_writer) if self.jobs.get() > 1 => return self.run_parallel(make_writer),
+            _ => (),
         }
+
+        let runner = self.runner();
+        self.programs.iter().map(|path| runner.exec(path)).collect()
     }
 
-    fn exec_with_exit(&self, path: &std::path::Path) {
-        println!("Compiling program `{}`...", path.display());
+    fn run_parallel(&self, make_writer: &WriterFactory<'a>) -> Vec<ProgramReport> {
+        let next = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel();
+        let (programs, options) = (&self.programs, self.options);
 
-        // Why `Err(_)`s? We use `DiagnosticHandler::emit_err()` in the earlier stages of compilation,
-        // that displays and returning error, so here we don't need this error for output/something else.
-        let program = match self.get_program(path) {
-            Ok(program) => program,
-            Err(_) => exit(1),
-        };
-        let res = match program.compile() {
-            Ok(program) => program,
-            Err(_) => exit(1),
-        };
+        thread::scope(|scope| {
+            for _ in 0..self.jobs.get().min(programs.len()) {
+                let sender = sender.clone();
+                let next = &next;
+                scope.spawn(move || {
+                    let out = Output::buffer();
+                    let (diag_ctxt, writer) = make_writer(out.clone());
+                    let runner = Runner {
+                        diag_ctxt: &diag_ctxt,
+                        writer: writer.as_ref(),
+                        options,
+                    };
 
-        match res.kind {
-            LiteralKind::Int { val } => println!("Result: {val}"),
-            LiteralKind::Float { val } => println!("Result: {val}"),
-        }
+                    // Threads take the next program when they are free,
+                    // so a long program doesn't hold back the others
+                    loop {
+                        let idx = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = programs.get(idx) else {
+                            break;
+                        };
+                        let report = runner.exec(path);
+                        if sender.send((idx, report, out.take())).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(sender);
+
+            // Programs are finished in any order, so the finished ones wait
+            // until all the programs before them are printed
+            let mut reports = Vec::with_capacity(programs.len());
+            let mut finished = BTreeMap::new();
+            for (idx, report, output) in receiver {
+                finished.insert(idx, (report, output));
+                while let Some((report, output)) = finished.remove(&reports.len()) {
+                    write!(self.out, "{output}");
+                    reports.push(report);
+                }
+            }
+
+            reports
+        })
     }
 
-    fn get_program(&self, path: &std::path::Path) -> Result<Program, Diagnostic> {
-        Program::from_source_file(path.to_string_lossy().to_string(), self.diag_ctxt)
+    /// Runs the session from the command line: exits with `1` if any program failed
+    pub fn run_with_exit(&self) {
+        match self.programs.as_slice() {
+            [path] if self.single_file => self.exec_with_exit(path),
+            _ => self.exec_many_with_exit(),
+        }
     }
 
-    fn read_cur_dir(&self) -> Result<std::fs::ReadDir, std::io::Error> {
-        self.get_cur_dir().read_dir()
+    fn exec_many_with_exit(&self) {
+        if self.programs.is_empty() {
+            let paths: Vec<String> = self.paths.iter().map(|path| format!("`{path}`")).collect();
+            self.writer.write_warning(&format!(
+                "not a single program was found in {}",
+                paths.join(", ")
+            ));
+            return;
+        }
+
+        // Every program is compiled even if the previous ones failed,
+        // so the summary shows the full picture
+        let reports = self.run();
+
+        self.writer.write_summary(&reports);
+        if reports.iter().any(|report| !report.passed) {
+            exit(1)
+        }
     }
 
-    fn get_cur_dir(&self) -> &std::path::Path {
-        self.cur.as_path()
+    fn exec_with_exit(&self, path: &Path) {
+        if !self.runner().exec(path).passed {
+            exit(1)
+        }
     }
 
-    fn is_valid_file(&self, path: &std::path::Path) -> bool {
-        if let Some(ext) = path.extension() {
-            ext == SOURCE_FILE_EXTENSION
-        } else {
-            false
+    fn runner(&self) -> Runner<'_> {
+        Runner {
+            diag_ctxt: self.diag_ctxt,
+            writer: self.writer.as_ref(),
+            options: self.options,
         }
     }
 }
@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::HashSet,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    compile::SOURCE_FILE_EXTENSION,
+    helpers::glob::{expand_glob, is_glob, is_hidden, split_glob},
+};
+
+/// A path given by the user that can't be read.
+#[derive(Debug)]
+pub struct PathError {
+    pub path: String,
+    pub err: io::Error,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to get `{}`: {}", self.path, self.err)
+    }
+}
+
+/// Returns absolute paths of the programs in the order they are compiled. Every path
+/// can be a file, a directory with programs (and its subdirectories if `recursive`) or a glob
+/// pattern like `examples/**/*.calc`. Programs of every path are sorted, and a program found
+/// by several paths is compiled only once, in the place of the first one.
+pub fn find_programs(paths: &[String], recursive: bool) -> Result<Vec<PathBuf>, PathError> {
+    let mut programs = Vec::new();
+    let mut seen = HashSet::new();
+
+    for path in paths {
+        let found = find_in_path(path, recursive).map_err(|err| PathError {
+            path: path.clone(),
+            err,
+        })?;
+        programs.extend(
+            found
+                .into_iter()
+                .filter(|program| seen.insert(program.clone())),
+        );
+    }
+
+    Ok(programs)
+}
+
+fn find_in_path(path: &str, recursive: bool) -> io::Result<Vec<PathBuf>> {
+    find_in_path_from(&std::env::current_dir()?, path, recursive)
+}
+
+// Paths are resolved against `cwd` once, so the session doesn't depend on the current directory
+fn find_in_path_from(cwd: &Path, path: &str, recursive: bool) -> io::Result<Vec<PathBuf>> {
+    if !is_glob(path) {
+        let resolved = std::path::absolute(cwd.join(path))?;
+        // A file given directly is compiled even with a wrong extension,
+        // so the user gets an error about it
+        return if resolved.metadata()?.is_dir() {
+            find_in_dir(&resolved, recursive)
+        } else {
+            Ok(vec![resolved])
+        };
+    }
+
+    // Only the literal part of the pattern is resolved,
+    // because names of `cwd` can contain wildcards too, like `w[1]`
+    let (root, comps) = split_glob(path);
+    let root = std::path::absolute(cwd.join(root))?;
+    let mut programs = Vec::new();
+    for path in expand_glob(&root, &comps)? {
+        if path.is_dir() {
+            programs.extend(find_in_dir(&path, recursive)?);
+        } else if is_program(&path) {
+            programs.push(path);
+        }
+    }
+    programs.sort();
+
+    Ok(programs)
+}
+
+pub fn find_in_dir(dir: &Path, recursive: bool) -> io::Result<Vec<PathBuf>> {
+    let mut programs = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        for entry in dir.read_dir()? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                if recursive && !is_hidden(&entry.file_name().to_string_lossy()) {
+                    dirs.push(path);
+                }
+            } else if is_program(&path) {
+                programs.push(path);
+            }
+        }
+    }
+    programs.sort();
+
+    Ok(programs)
+}
+
+fn is_program(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == SOURCE_FILE_EXTENSION)
+}
@@ -0,0 +1,75 @@
+use std::{fs, path::PathBuf};
+
+use super::{find_in_path_from, find_programs};
+
+#[test]
+fn test_find_programs() {
+    let dir = std::env::temp_dir().join(format!("calc-discover-{}", std::process::id()));
+    for file in [
+        "b.calc",
+        "a.calc",
+        "notes.txt",
+        "sub/c.calc",
+        "sub/deep/d.calc",
+        ".git/e.calc",
+    ] {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "1").unwrap();
+    }
+    let find = |paths: &[&str], recursive| {
+        let paths: Vec<String> = paths
+            .iter()
+            .map(|path| format!("{}/{path}", dir.display()))
+            .collect();
+        find_programs(&paths, recursive)
+            .unwrap()
+            .into_iter()
+            .map(|path| path.strip_prefix(&dir).unwrap().to_path_buf())
+            .collect::<Vec<_>>()
+    };
+    let paths = |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+
+    assert_eq!(find(&[""], false), paths(&["a.calc", "b.calc"]));
+    assert_eq!(
+        find(&[""], true),
+        paths(&["a.calc", "b.calc", "sub/c.calc", "sub/deep/d.calc"])
+    );
+    assert_eq!(
+        find(&["**/*.calc"], false),
+        paths(&["a.calc", "b.calc", "sub/c.calc", "sub/deep/d.calc"])
+    );
+    assert_eq!(find(&["s*"], false), paths(&["sub/c.calc"]));
+    // Files given directly are compiled whatever their extension is
+    assert_eq!(find(&["notes.txt"], false), paths(&["notes.txt"]));
+    // Paths keep their order, and programs are compiled once
+    assert_eq!(
+        find(&["sub", "b.calc", "*.calc"], false),
+        paths(&["sub/c.calc", "b.calc", "a.calc"])
+    );
+
+    let err = find_programs(&[format!("{}/none", dir.display())], false).unwrap_err();
+    assert_eq!(err.err.kind(), std::io::ErrorKind::NotFound);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_find_programs_from_dir_with_wildcards() {
+    let dir = std::env::temp_dir()
+        .join(format!("calc-discover-glob-{}", std::process::id()))
+        .join("w[1]");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("a.calc"), "1").unwrap();
+
+    assert_eq!(
+        find_in_path_from(&dir, "*.calc", false).unwrap(),
+        [dir.join("a.calc")]
+    );
+    assert_eq!(
+        find_in_path_from(&dir, "./*.calc", false).unwrap(),
+        [dir.join("a.calc")]
+    );
+
+    fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+}
@@ -0,0 +1,15 @@
+use crate::errors::diagnostic::{Diagnostic, DiagnosticCtxt, DiagnosticMsg, IntoDiagnostic};
+use calculator_macros::IntoDiagnostic;
+
+#[derive(IntoDiagnostic)]
+#[diagnostic("failed to write file with path `{}`: {}")]
+pub struct WriteFileError {
+    path: String,
+    msg: String,
+}
+
+impl WriteFileError {
+    pub fn new(path: String, msg: String) -> Self {
+        Self { path, msg }
+    }
+}
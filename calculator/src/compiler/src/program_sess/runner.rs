@@ -0,0 +1,86 @@
+use std::{fs, path::Path};
+
+use super::{errors::WriteFileError, ProgramReport, ResultWriter};
+use crate::{
+    compile::{Codegen, Compile, CompileOptions, Emit, Program, Target},
+    errors::diagnostic::{Diagnostic, DiagnosticCtxt},
+};
+
+/// Compiles programs one by one with the same diagnostic context and writer.
+/// Every thread of `ProgramSess` has its own runner, because `DiagnosticCtxt` can't be shared.
+pub struct Runner<'a> {
+    pub diag_ctxt: &'a DiagnosticCtxt,
+    pub writer: &'a dyn ResultWriter,
+    pub options: CompileOptions,
+}
+
+impl<'a> Runner<'a> {
+    pub fn exec(&self, path: &Path) -> ProgramReport {
+        self.writer.start(path);
+        let errs_before = self.diag_ctxt.err_count();
+        let warns_before = self.diag_ctxt.warn_count();
+
+        let passed = self.exec_program(path).is_ok();
+        if !passed {
+            self.writer
+                .write_failure(path, self.diag_ctxt.err_count() - errs_before);
+        }
+
+        ProgramReport {
+            path: path.to_path_buf(),
+            passed,
+            warnings: self.diag_ctxt.warn_count() - warns_before,
+        }
+    }
+
+    // Errors are emitted by `DiagnosticHandler::emit_err()` in the stages of compilation,
+    // so here the returned error only tells that the program failed
+    fn exec_program(&self, path: &Path) -> Result<(), Diagnostic<'a>> {
+        let program = self.get_program(path)?;
+        // Generated code is written next to the program instead of printing the result
+        if let Some(target) = Target::from_emit(self.options.emit) {
+            return self.emit_code(&program, target, path);
+        }
+        let res = program.compile()?;
+
+        match self.options.emit {
+            Emit::Result => self.writer.write_result(path, &res.kind),
+            Emit::Simplified => {
+                self.writer
+                    .write_output(path, "Simplified", &program.get_ast().to_string())
+            }
+            Emit::C | Emit::Asm | Emit::Llvm => unreachable!("Code is emitted above"),
+        }
+
+        Ok(())
+    }
+
+    fn emit_code(
+        &self,
+        program: &Program<'a>,
+        target: Target,
+        path: &Path,
+    ) -> Result<(), Diagnostic<'a>> {
+        let code = Codegen::new(program, target).compile()?;
+
+        let out_path = path.with_extension(target.extension());
+        if let Err(err) = fs::write(&out_path, code) {
+            return Err(self.diag_ctxt.handle().emit_err(WriteFileError::new(
+                out_path.to_string_lossy().to_string(),
+                err.to_string(),
+            )));
+        }
+        self.writer
+            .write_output(path, "Emitted", &out_path.to_string_lossy());
+
+        Ok(())
+    }
+
+    fn get_program(&self, path: &Path) -> Result<Program<'a>, Diagnostic<'a>> {
+        Program::from_source_file(
+            path.to_string_lossy().to_string(),
+            self.diag_ctxt,
+            self.options,
+        )
+    }
+}
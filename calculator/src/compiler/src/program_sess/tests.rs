@@ -0,0 +1,103 @@
+use std::{env, fs, num::NonZeroUsize};
+
+use super::{OutputFormat, ProgramReport, ProgramSess, TextWriter};
+use crate::{
+    compile::CompileOptions,
+    errors::{diagnostic::DiagnosticCtxt, emitter::StdoutEmitter},
+    helpers::output::Output,
+};
+
+#[test]
+fn test_sess_from_dir() {
+    let dir = env::temp_dir().join(format!("calc-sess-{}", std::process::id()));
+    for (file, src) in [
+        ("a.calc", "1 + 2"),
+        ("b.calc", "1 / 0"),
+        ("sub/c.calc", "1 + 0.5"),
+    ] {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, src).unwrap();
+    }
+    let cur_dir = env::current_dir().unwrap();
+
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(StdoutEmitter::new()));
+    let psess = ProgramSess::from_dir(
+        &dir,
+        true,
+        &diag_ctxt,
+        CompileOptions::default(),
+        Box::new(TextWriter::default()),
+    )
+    .unwrap();
+    let report = |file: &str, passed, warnings| ProgramReport {
+        path: dir.join(file),
+        passed,
+        warnings,
+    };
+
+    assert!(psess.programs().iter().all(|path| path.is_absolute()));
+    assert_eq!(
+        psess.run(),
+        [
+            report("a.calc", true, 0),
+            report("b.calc", false, 0),
+            report("sub/c.calc", true, 1),
+        ]
+    );
+    assert_eq!(env::current_dir().unwrap(), cur_dir);
+
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(ProgramSess::from_dir(
+        &dir,
+        false,
+        &diag_ctxt,
+        CompileOptions::default(),
+        Box::new(TextWriter::default()),
+    )
+    .is_err());
+}
+
+#[test]
+fn test_sess_jobs() {
+    let dir = env::temp_dir().join(format!("calc-jobs-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for i in 0..20 {
+        let src = match i % 3 {
+            0 => format!("{i} + 1"),
+            1 => format!("{i} / 0"),
+            _ => format!("{i} + 0.5"),
+        };
+        fs::write(dir.join(format!("{i:02}.calc")), src).unwrap();
+    }
+    let run = |jobs| {
+        let out = Output::buffer();
+        let (diag_ctxt, writer) = OutputFormat::Text.diag_ctxt_and_writer(out.clone());
+        let reports =
+            ProgramSess::from_dir(&dir, false, &diag_ctxt, CompileOptions::default(), writer)
+                .unwrap()
+                .with_jobs(NonZeroUsize::new(jobs).unwrap(), |out| {
+                    OutputFormat::Text.diag_ctxt_and_writer(out)
+                })
+                .with_output(out.clone())
+                .run();
+
+        (reports, out.take())
+    };
+
+    let (reports, output) = run(1);
+    assert_eq!(reports.len(), 20);
+    let compiled: Vec<&str> = output
+        .lines()
+        .filter_map(|line| line.strip_prefix("Compiling program "))
+        .collect();
+    let programs: Vec<String> = (0..20)
+        .map(|i| format!("`{}`...", dir.join(format!("{i:02}.calc")).display()))
+        .collect();
+    assert_eq!(compiled, programs);
+    // Reports and output are in the order of programs, whichever thread compiled them
+    assert_eq!(run(4), (reports.clone(), output.clone()));
+    assert_eq!(run(64), (reports, output));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,257 @@
+#[cfg(test)]
+mod tests;
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    ast_lowering::ast::LiteralKind,
+    errors::{
+        diagnostic::DiagnosticCtxt,
+        emitter::{JsonDiags, JsonEmitter, StdoutEmitter},
+    },
+    helpers::{
+        json::{json_opt_string, json_string},
+        output::Output,
+    },
+};
+
+/// How the results of programs are printed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Text for humans, diagnostics are printed with the code they point to
+    #[default]
+    Text,
+    /// One JSON object per program with its result and diagnostics
+    Json,
+}
+
+impl OutputFormat {
+    /// Creates the diagnostic context and the writer that print in this format to `out`.
+    /// Diagnostics are printed by the emitter in the text format,
+    /// and together with the result in JSON
+    pub fn diag_ctxt_and_writer(self, out: Output) -> (DiagnosticCtxt, Box<dyn ResultWriter>) {
+        match self {
+            OutputFormat::Text => (
+                DiagnosticCtxt::new(Box::new(StdoutEmitter::new().with_output(out.clone()))),
+                Box::new(TextWriter::default().with_output(out)),
+            ),
+            OutputFormat::Json => {
+                let diags = JsonDiags::default();
+                (
+                    DiagnosticCtxt::new(Box::new(JsonEmitter::new(diags.clone()))),
+                    Box::new(JsonWriter::new(diags).with_output(out)),
+                )
+            }
+        }
+    }
+}
+
+/// What happened with a program of the session, it's shown in the summary of a directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProgramReport {
+    pub path: PathBuf,
+    pub passed: bool,
+    pub warnings: usize,
+}
+
+/// Prints what happened with every program of `ProgramSess`.
+/// Diagnostics are printed by the `Emitter` of the session.
+pub trait ResultWriter: std::fmt::Debug {
+    fn start(&self, path: &Path);
+    fn write_result(&self, path: &Path, res: &LiteralKind);
+    /// Something else than the result is printed for the program, like the simplified program
+    /// or the path of the generated code
+    fn write_output(&self, path: &Path, label: &str, output: &str);
+    fn write_failure(&self, path: &Path, errs: usize);
+    /// Called after all programs of a directory are compiled
+    fn write_summary(&self, reports: &[ProgramReport]);
+    /// Messages of the session that are not about a program,
+    /// like a path that can't be read or a directory without programs
+    fn write_error(&self, msg: &str);
+    fn write_warning(&self, msg: &str);
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TextWriter {
+    out: Output,
+}
+
+impl TextWriter {
+    pub fn with_output(mut self, out: Output) -> Self {
+        self.out = out;
+        self
+    }
+}
+
+impl ResultWriter for TextWriter {
+    fn start(&self, path: &Path) {
+        writeln!(self.out, "Compiling program `{}`...", path.display());
+    }
+
+    fn write_result(&self, _: &Path, res: &LiteralKind) {
+        writeln!(self.out, "Result: {res}");
+    }
+
+    fn write_output(&self, _: &Path, label: &str, output: &str) {
+        writeln!(self.out, "{label}: {output}");
+    }
+
+    // All errors are already emitted, so we only tell how many of them the program has
+    fn write_failure(&self, path: &Path, errs: usize) {
+        writeln!(
+            self.out,
+            "Error: could not compile `{}` due to {errs} previous error{}",
+            path.display(),
+            if errs == 1 { "" } else { "s" }
+        );
+    }
+
+    fn write_summary(&self, reports: &[ProgramReport]) {
+        write!(self.out, "{}", render_summary_table(reports));
+    }
+
+    fn write_error(&self, msg: &str) {
+        writeln!(self.out, "{msg}");
+    }
+
+    fn write_warning(&self, msg: &str) {
+        writeln!(self.out, "Warning: {msg}");
+    }
+}
+
+/// Renders the table with the status of every program and the totals:
+/// ```text
+/// Summary:
+///  status | warnings | program
+///  passed |        0 | examples/int_add.calc
+///  failed |        2 | examples/wrong.calc
+/// 1 passed, 1 failed, 2 warnings
+/// ```
+pub fn render_summary_table(reports: &[ProgramReport]) -> String {
+    let mut table = String::from("Summary:\n status | warnings | program\n");
+    for ProgramReport {
+        path,
+        passed,
+        warnings,
+    } in reports
+    {
+        let status = if *passed { "passed" } else { "failed" };
+        table.push_str(&format!(" {status} | {warnings:>8} | {}\n", path.display()));
+    }
+
+    let (passed, failed, warnings) = totals(reports);
+    table.push_str(&format!(
+        "{passed} passed, {failed} failed, {warnings} warning{}\n",
+        if warnings == 1 { "" } else { "s" }
+    ));
+
+    table
+}
+
+// Number of passed and failed programs and of all warnings
+fn totals(reports: &[ProgramReport]) -> (usize, usize, usize) {
+    let passed = reports.iter().filter(|report| report.passed).count();
+    let warnings = reports.iter().map(|report| report.warnings).sum();
+
+    (passed, reports.len() - passed, warnings)
+}
+
+/// Prints one line with a JSON object for every program:
+/// ```text
+/// {"path":"examples/int_add.calc","status":"ok","type":"Int","value":"6","output":null,"diagnostics":[]}
+/// ```
+/// Values are strings, so big numbers, `inf` and `NaN` are not changed. Diagnostics are taken
+/// from the `JsonEmitter` that shares `diags` with the writer.
+#[derive(Debug, Clone, Default)]
+pub struct JsonWriter {
+    diags: JsonDiags,
+    out: Output,
+}
+
+impl JsonWriter {
+    pub fn new(diags: JsonDiags) -> Self {
+        Self {
+            diags,
+            out: Output::Stdout,
+        }
+    }
+
+    pub fn with_output(mut self, out: Output) -> Self {
+        self.out = out;
+        self
+    }
+
+    fn render(
+        &self,
+        path: &Path,
+        status: &str,
+        res: Option<&LiteralKind>,
+        output: Option<&str>,
+    ) -> String {
+        let diags = self.diags.take().join(",");
+
+        format!(
+            "{{\"path\":{},\"status\":{},\"type\":{},\"value\":{},\"output\":{},\"diagnostics\":[{diags}]}}",
+            json_string(&path.to_string_lossy()),
+            json_string(status),
+            json_opt_string(res.map(LiteralKind::type_name)),
+            json_opt_string(res.map(LiteralKind::to_string).as_deref()),
+            json_opt_string(output),
+        )
+    }
+}
+
+impl ResultWriter for JsonWriter {
+    // Diagnostics of the previous programs are already printed
+    fn start(&self, _: &Path) {
+        self.diags.borrow_mut().clear();
+    }
+
+    fn write_result(&self, path: &Path, res: &LiteralKind) {
+        writeln!(self.out, "{}", self.render(path, "ok", Some(res), None));
+    }
+
+    fn write_output(&self, path: &Path, _: &str, output: &str) {
+        writeln!(self.out, "{}", self.render(path, "ok", None, Some(output)));
+    }
+
+    fn write_failure(&self, path: &Path, _: usize) {
+        writeln!(self.out, "{}", self.render(path, "error", None, None));
+    }
+
+    fn write_summary(&self, reports: &[ProgramReport]) {
+        writeln!(self.out, "{}", render_summary_json(reports));
+    }
+
+    fn write_error(&self, msg: &str) {
+        writeln!(self.out, "{}", render_message_json("error", msg));
+    }
+
+    fn write_warning(&self, msg: &str) {
+        writeln!(self.out, "{}", render_message_json("warning", msg));
+    }
+}
+
+/// Renders a message of the session like a diagnostic that is not related to the code,
+/// so every printed line is still a JSON object:
+/// ```text
+/// {"level":"warning","message":"not a single program was found in `dir`","span":null,"help":null}
+/// ```
+pub fn render_message_json(level: &str, msg: &str) -> String {
+    format!(
+        "{{\"level\":{},\"message\":{},\"span\":null,\"help\":null}}",
+        json_string(level),
+        json_string(msg)
+    )
+}
+
+/// Renders the totals of the directory as a JSON object, statuses of the programs
+/// are already printed with their results:
+/// ```text
+/// {"summary":{"passed":1,"failed":1,"warnings":2}}
+/// ```
+pub fn render_summary_json(reports: &[ProgramReport]) -> String {
+    let (passed, failed, warnings) = totals(reports);
+
+    format!("{{\"summary\":{{\"passed\":{passed},\"failed\":{failed},\"warnings\":{warnings}}}}}")
+}
@@ -0,0 +1,95 @@
+use std::path::{Path, PathBuf};
+
+use super::{
+    render_message_json, render_summary_json, render_summary_table, JsonWriter, ProgramReport,
+};
+use crate::{ast_lowering::ast::LiteralKind, errors::emitter::JsonDiags};
+
+#[test]
+fn test_json_result() {
+    let diags = JsonDiags::default();
+    diags
+        .borrow_mut()
+        .push(r#"{"level":"warning","message":"w","span":null,"help":null}"#.to_string());
+    let writer = JsonWriter::new(diags.clone());
+
+    assert_eq!(
+        writer.render(
+            Path::new("dir/a \"b\".calc"),
+            "ok",
+            Some(&LiteralKind::Float { val: f64::NAN }),
+            None
+        ),
+        r#"{"path":"dir/a \"b\".calc","status":"ok","type":"Float","value":"NaN","output":null,"diagnostics":[{"level":"warning","message":"w","span":null,"help":null}]}"#
+    );
+    // Diagnostics are printed only with their program
+    assert!(diags.borrow().is_empty());
+}
+
+#[test]
+fn test_json_failure_and_output() {
+    let writer = JsonWriter::default();
+
+    assert_eq!(
+        writer.render(Path::new("a.calc"), "error", None, None),
+        r#"{"path":"a.calc","status":"error","type":null,"value":null,"output":null,"diagnostics":[]}"#
+    );
+    assert_eq!(
+        writer.render(Path::new("a.calc"), "ok", None, Some("let x = 6\nx")),
+        r#"{"path":"a.calc","status":"ok","type":null,"value":null,"output":"let x = 6\nx","diagnostics":[]}"#
+    );
+}
+
+fn reports() -> Vec<ProgramReport> {
+    vec![
+        ProgramReport {
+            path: PathBuf::from("examples/int_add.calc"),
+            passed: true,
+            warnings: 0,
+        },
+        ProgramReport {
+            path: PathBuf::from("examples/wrong.calc"),
+            passed: false,
+            warnings: 12,
+        },
+        ProgramReport {
+            path: PathBuf::from("examples/types.calc"),
+            passed: true,
+            warnings: 1,
+        },
+    ]
+}
+
+#[test]
+fn test_summary_table() {
+    assert_eq!(
+        render_summary_table(&reports()),
+        "Summary:
+ status | warnings | program
+ passed |        0 | examples/int_add.calc
+ failed |       12 | examples/wrong.calc
+ passed |        1 | examples/types.calc
+2 passed, 1 failed, 13 warnings
+"
+    );
+    assert_eq!(
+        render_summary_table(&reports()[2..]).lines().last(),
+        Some("1 passed, 0 failed, 1 warning")
+    );
+}
+
+#[test]
+fn test_summary_json() {
+    assert_eq!(
+        render_summary_json(&reports()),
+        r#"{"summary":{"passed":2,"failed":1,"warnings":13}}"#
+    );
+}
+
+#[test]
+fn test_json_message() {
+    assert_eq!(
+        render_message_json("warning", "not a single program was found in `dir`"),
+        r#"{"level":"warning","message":"not a single program was found in `dir`","span":null,"help":null}"#
+    );
+}
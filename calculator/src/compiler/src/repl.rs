@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod tests;
+
+use std::io::{self, BufRead, IsTerminal as _, Write};
+
+use crate::{
+    compile::{Codegen, CompileOptions, Emit, Env, Program, Target},
+    errors::diagnostic::DiagnosticCtxt,
+};
+
+// The "path" of every program that was typed in the REPL
+pub const REPL_PATH: &str = "<repl>";
+const PROMPT: &str = ">> ";
+
+/// Reads programs line by line, compiles them and prints their results.
+/// Unlike `ProgramSess`, it doesn't exit after the first diagnostic:
+/// the diagnostic is already emitted, so we just wait for the next line.
+/// Variables defined in one line are available in the next ones.
+pub struct Repl<'a> {
+    diag_ctxt: &'a DiagnosticCtxt,
+    options: CompileOptions,
+}
+
+impl<'a> Repl<'a> {
+    pub fn new(diag_ctxt: &'a DiagnosticCtxt, options: CompileOptions) -> Self {
+        Self { diag_ctxt, options }
+    }
+
+    pub fn run(&self) -> io::Result<()> {
+        let stdin = io::stdin();
+        // Dont print prompt if the input is piped, for example, `cat file | calc repl`
+        let with_prompt = stdin.is_terminal();
+
+        self.run_with(stdin.lock(), io::stdout(), with_prompt)
+    }
+
+    pub fn run_with(
+        &self,
+        mut input: impl BufRead,
+        mut output: impl Write,
+        with_prompt: bool,
+    ) -> io::Result<()> {
+        let mut line = String::new();
+        let mut env = Env::new();
+
+        loop {
+            if with_prompt {
+                write!(output, "{PROMPT}")?;
+                output.flush()?;
+            }
+
+            line.clear();
+            // `0` means that we reached EOF (for example, after `Ctrl+D`)
+            if input.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+
+            let src = line.trim();
+            if src.is_empty() {
+                continue;
+            }
+
+            self.eval(src, &mut env, &mut output)?;
+        }
+    }
+
+    fn eval(&self, src: &str, env: &mut Env, output: &mut impl Write) -> io::Result<()> {
+        // Why `Err(_)`s? All diagnostics were already emitted by the compiler stages,
+        // so we can just skip this line.
+        let program = match Program::from_source_in(
+            src,
+            REPL_PATH.to_string(),
+            self.diag_ctxt,
+            env,
+            self.options,
+        ) {
+            Ok(program) => program,
+            Err(_) => return Ok(()),
+        };
+
+        // Generated code also calculates the program, so its variables are defined
+        if let Some(target) = Target::from_emit(self.options.emit) {
+            return match Codegen::new(&program, target).compile_in(env) {
+                Ok(code) => write!(output, "{code}"),
+                Err(_) => Ok(()),
+            };
+        }
+
+        // The program is still calculated, so its variables are defined for the next lines
+        match (program.compile_in(env), self.options.emit) {
+            (Ok(res), Emit::Result) => writeln!(output, "{}", res.kind),
+            (Ok(_), Emit::Simplified) => writeln!(output, "{}", program.get_ast()),
+            (Ok(_), Emit::C | Emit::Asm | Emit::Llvm) => unreachable!("Code is emitted above"),
+            (Err(_), _) => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::{cell::Cell, rc::Rc};
+
+use super::Repl;
+use crate::{
+    compile::{CompileOptions, Emit},
+    errors::{
+        diagnostic::{Diagnostic, DiagnosticCtxt},
+        emitter::Emitter,
+    },
+};
+
+#[derive(Debug, Default)]
+struct CountEmitter {
+    errs: Rc<Cell<usize>>,
+}
+
+impl Emitter for CountEmitter {
+    fn emit_diag(&self, _: &Diagnostic) {
+        self.errs.set(self.errs.get() + 1);
+    }
+
+    fn emit_warn(&self, _: &Diagnostic) {}
+}
+
+// Returns the output and the number of reported errors
+fn run_repl(input: &str) -> (String, usize) {
+    run_repl_with(input, CompileOptions::default())
+}
+
+fn run_repl_with(input: &str, options: CompileOptions) -> (String, usize) {
+    let emitter = CountEmitter::default();
+    let errs = emitter.errs.clone();
+    let diag_ctxt = DiagnosticCtxt::new(Box::new(emitter));
+    let mut output = Vec::new();
+
+    Repl::new(&diag_ctxt, options)
+        .run_with(input.as_bytes(), &mut output, false)
+        .unwrap();
+
+    (String::from_utf8(output).unwrap(), errs.get())
+}
+
+#[test]
+fn test_repl_eval_lines() {
+    assert_eq!(
+        run_repl("2 + 2 * 2\n\n(1 + 2) * 3\n"),
+        ("6\n9\n".to_string(), 0)
+    );
+}
+
+#[test]
+fn test_repl_continues_after_error() {
+    // Both invalid lines are reported, and the last one is still calculated
+    assert_eq!(run_repl("1 +\n(2 + 3\n10 / 4.0"), ("2.5\n".to_string(), 2));
+}
+
+#[test]
+fn test_repl_keeps_variables() {
+    assert_eq!(
+        run_repl("let x = 2 * 3\nlet x = 1\nlet y = x + 1; x * y\nz\n"),
+        ("6\n42\n".to_string(), 2)
+    );
+}
+
+#[test]
+fn test_repl_constants() {
+    assert_eq!(
+        run_repl("let e = 1\nlet r = 2.0\npi * r ** 2 - inf\n"),
+        ("2\n-inf\n".to_string(), 1)
+    );
+}
+
+#[test]
+fn test_repl_emit_simplified() {
+    let options = CompileOptions {
+        emit: Emit::Simplified,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        run_repl_with("let x = 2 * 3\nx * 1 + (4 - 4)\n", options),
+        ("let x = 6\nx\n".to_string(), 0)
+    );
+}
+
+#[test]
+fn test_repl_emit_c() {
+    let options = CompileOptions {
+        emit: Emit::C,
+        ..Default::default()
+    };
+    let (output, errs) = run_repl_with("let x = 2 * 3\nx + 1\n", options);
+    assert_eq!(errs, 0);
+
+    assert!(output.contains("const int64_t var_0 = (INT64_C(6));"));
+    assert!(output.contains("printf(\"%\" PRId64 \"\\n\", ((INT64_C(6)) + (INT64_C(1))));"));
+}
@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod tests;
+
+/// Location of some code in the source file in bytes: `lo` is inclusive and `hi` is exclusive.
+/// Lines and columns are calculated only when we need them (for example, to display a diagnostic)
+/// using the `SourceFile`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Span {
+    pub lo: usize,
+    pub hi: usize,
+}
+
+/// Line and column of some byte in the source file. Both start at 1, as in most editors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Loc {
+    pub line: usize,
+    pub col: usize,
+}
+
+/// Source code of the program and its "path" that we use to display diagnostics.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceFile {
+    name: String,
+    src: String,
+    // Byte offset of the start of each line
+    line_starts: Vec<usize>,
+}
+
+impl Span {
+    pub fn new(lo: usize, hi: usize) -> Self {
+        Self { lo, hi }
+    }
+
+    /// Returns a span that covers both `self` and `other`
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.lo.min(other.lo), self.hi.max(other.hi))
+    }
+}
+
+impl SourceFile {
+    pub fn new(name: String, src: String) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(src.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        Self {
+            name,
+            src,
+            line_starts,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_src(&self) -> &str {
+        &self.src
+    }
+
+    pub fn lookup_loc(&self, pos: usize) -> Loc {
+        let pos = pos.min(self.src.len());
+        // Index of the last line that starts before or at `pos`
+        let line = self.line_starts.partition_point(|&start| start <= pos) - 1;
+        let line_start = self.line_starts[line];
+
+        Loc {
+            line: line + 1,
+            col: self.src[line_start..pos].chars().count() + 1,
+        }
+    }
+
+    /// Returns the line with number `line` (starting from 1) without a line break
+    pub fn get_line(&self, line: usize) -> Option<&str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line)
+            .copied()
+            .unwrap_or(self.src.len());
+
+        Some(self.src[start..end].trim_end_matches(['\n', '\r']))
+    }
+}
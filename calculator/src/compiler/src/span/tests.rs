@@ -0,0 +1,18 @@
+use super::{Loc, SourceFile, Span};
+
+#[test]
+fn test_lookup_loc() {
+    let source_file = SourceFile::new("test.calc".to_string(), "1 +\n  2 * x\n".to_string());
+
+    assert_eq!(source_file.lookup_loc(0), Loc { line: 1, col: 1 });
+    assert_eq!(source_file.lookup_loc(3), Loc { line: 1, col: 4 });
+    assert_eq!(source_file.lookup_loc(6), Loc { line: 2, col: 3 });
+    assert_eq!(source_file.lookup_loc(12), Loc { line: 3, col: 1 });
+    assert_eq!(source_file.get_line(2), Some("  2 * x"));
+    assert_eq!(source_file.get_line(4), None);
+}
+
+#[test]
+fn test_span_to() {
+    assert_eq!(Span::new(4, 6).to(Span::new(1, 2)), Span::new(1, 6));
+}